@@ -0,0 +1,128 @@
+use array2d::Array2D;
+use std::cmp::Reverse;
+use std::collections::{ BinaryHeap, HashMap, VecDeque };
+use std::time::Instant;
+
+use crate::maze::{ cell_on_next_floor, neighbour_in_direction, MazeCell, MazeState, ALL_DIRECTIONS };
+use crate::solve::{ SearchNode, SearchProblem, SearchState, Solution, Unsolvable };
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Heuristic {
+    // grid distance to the nearest exit, ignoring walls but not stairs and teleporters
+    Manhattan,
+    // exact distance to the nearest exit through the passages, ignoring doors and gates
+    RelaxedDistance,
+}
+
+// distance of every cell to the nearest exit, or None if no exit can be reached from it
+pub fn exit_distance_map(maze_table: &Array2D<MazeCell>, heuristic: Heuristic) -> Array2D<Option<usize>> {
+    let mut distances = Array2D::filled_with(None, maze_table.num_rows(), maze_table.num_columns());
+    let mut queue: VecDeque<(usize, usize)> = VecDeque::new();
+    for maze_cell in maze_table.elements_row_major_iter() {
+        if maze_cell.end_of_maze {
+            distances[(maze_cell.row_index, maze_cell.col_index)] = Some(0);
+            queue.push_back((maze_cell.row_index, maze_cell.col_index));
+        }
+    }
+
+    // multi-source BFS from the exits, walking every passage backwards
+    while let Some(position) = queue.pop_front() {
+        let distance = distances[position].unwrap();
+        let mut predecessors: Vec<(usize, usize)> = Vec::new();
+        for direction in &ALL_DIRECTIONS {
+            let Some(neighbour_position) = neighbour_in_direction(maze_table, position, direction) else { continue };
+            let passable = match heuristic {
+                Heuristic::Manhattan => true,
+                Heuristic::RelaxedDistance => maze_table[neighbour_position].available_directions.contains(&direction.opposite()),
+            };
+            if passable {
+                predecessors.push(neighbour_position);
+            }
+        }
+
+        // stairs from the floor above or below
+        if let Some(above_position) = cell_on_next_floor(maze_table, position, true) {
+            if maze_table[above_position].stairs_down {
+                predecessors.push(above_position);
+            }
+        }
+        if let Some(below_position) = cell_on_next_floor(maze_table, position, false) {
+            if maze_table[below_position].stairs_up {
+                predecessors.push(below_position);
+            }
+        }
+
+        // a teleporter partner is one move away from its pad, and from the pad's neighbours when teleporting is forced
+        if let Some(pad_position) = maze_table[position].teleport_to {
+            predecessors.push(pad_position);
+            for direction in &ALL_DIRECTIONS {
+                if let Some(neighbour_position) = neighbour_in_direction(maze_table, pad_position, direction) {
+                    predecessors.push(neighbour_position);
+                }
+            }
+        }
+
+        for predecessor in predecessors {
+            if distances[predecessor].is_none() {
+                distances[predecessor] = Some(distance + 1);
+                queue.push_back(predecessor);
+            }
+        }
+    }
+
+    return distances;
+}
+
+pub fn solve_maze_astar(maze_table: &Array2D<MazeCell>, initial_maze_state: &MazeState, heuristic: Heuristic) -> Result<Solution, Unsolvable> {
+    let now = Instant::now();
+    let mut states_expanded = 0;
+
+    let problem = SearchProblem::new(maze_table, initial_maze_state);
+    let exit_distances = exit_distance_map(maze_table, heuristic);
+
+    let initial_node = problem.initial_node();
+    let Some(initial_estimate) = exit_distances[initial_node.state.position()] else {
+        return Err(Unsolvable { states_expanded, elapsed: now.elapsed() });
+    };
+
+    // lowest number of moves found so far for every state
+    let mut best_moves: HashMap<SearchState, usize> = HashMap::new();
+    best_moves.insert(initial_node.state.clone(), 0);
+
+    let mut search_nodes: Vec<SearchNode> = vec![initial_node];
+    let mut moves: Vec<usize> = vec![0];
+
+    // ordered by estimated total length, preferring nodes further from the start on ties
+    let mut open_list: BinaryHeap<(Reverse<usize>, usize, Reverse<usize>)> = BinaryHeap::new();
+    open_list.push((Reverse(initial_estimate), 0, Reverse(0)));
+
+    while let Some((_, _, Reverse(node_index))) = open_list.pop() {
+        let state = &search_nodes[node_index].state;
+        // a shorter path to this state was pushed after this one
+        if best_moves[state] < moves[node_index] {
+            continue;
+        }
+
+        // found end of maze
+        if problem.is_goal(state) {
+            return Ok(Solution::new(&problem, &search_nodes, node_index, states_expanded, now.elapsed()));
+        }
+        states_expanded += 1;
+
+        let neighbour_moves = moves[node_index] + 1;
+        for neighbour_node in problem.successors(node_index, state) {
+            // cells that can't reach an exit even with every door open are dropped
+            let Some(estimate) = exit_distances[neighbour_node.state.position()] else { continue };
+            if best_moves.get(&neighbour_node.state).is_some_and(|best| *best <= neighbour_moves) {
+                continue;
+            }
+
+            best_moves.insert(neighbour_node.state.clone(), neighbour_moves);
+            open_list.push((Reverse(neighbour_moves + estimate), neighbour_moves, Reverse(search_nodes.len())));
+            search_nodes.push(neighbour_node);
+            moves.push(neighbour_moves);
+        }
+    }
+
+    return Err(Unsolvable { states_expanded, elapsed: now.elapsed() });
+}
@@ -0,0 +1,54 @@
+use array2d::Array2D;
+use std::cmp::Reverse;
+use std::collections::{ BinaryHeap, HashMap };
+use std::time::Instant;
+
+use crate::maze::{ MazeCell, MazeState };
+use crate::solve::{ SearchNode, SearchProblem, SearchState, Solution, Unsolvable };
+
+// uniform-cost search, minimizes the total cost of the moves instead of their number
+pub fn solve_maze_dijkstra(maze_table: &Array2D<MazeCell>, initial_maze_state: &MazeState) -> Result<Solution, Unsolvable> {
+    let now = Instant::now();
+    let mut states_expanded = 0;
+
+    let problem = SearchProblem::new(maze_table, initial_maze_state);
+    let initial_node = problem.initial_node();
+
+    // lowest cost found so far for every state
+    let mut best_costs: HashMap<SearchState, u64> = HashMap::new();
+    best_costs.insert(initial_node.state.clone(), 0);
+
+    let mut search_nodes: Vec<SearchNode> = vec![initial_node];
+    let mut costs: Vec<u64> = vec![0];
+
+    let mut open_list: BinaryHeap<(Reverse<u64>, Reverse<usize>)> = BinaryHeap::new();
+    open_list.push((Reverse(0), Reverse(0)));
+
+    while let Some((_, Reverse(node_index))) = open_list.pop() {
+        let state = &search_nodes[node_index].state;
+        // a cheaper path to this state was pushed after this one
+        if best_costs[state] < costs[node_index] {
+            continue;
+        }
+
+        // found end of maze
+        if problem.is_goal(state) {
+            return Ok(Solution::new(&problem, &search_nodes, node_index, states_expanded, now.elapsed()));
+        }
+        states_expanded += 1;
+
+        for neighbour_node in problem.successors(node_index, state) {
+            let neighbour_cost = costs[node_index] + neighbour_node.move_cost;
+            if best_costs.get(&neighbour_node.state).is_some_and(|best| *best <= neighbour_cost) {
+                continue;
+            }
+
+            best_costs.insert(neighbour_node.state.clone(), neighbour_cost);
+            open_list.push((Reverse(neighbour_cost), Reverse(search_nodes.len())));
+            search_nodes.push(neighbour_node);
+            costs.push(neighbour_cost);
+        }
+    }
+
+    return Err(Unsolvable { states_expanded, elapsed: now.elapsed() });
+}
@@ -0,0 +1,205 @@
+use array2d::Array2D;
+use std::collections::{ HashSet, VecDeque };
+use std::time::{ Duration, Instant };
+
+use crate::maze::{ cell_on_next_floor, neighbour_in_direction, Hazard, MazeCell, MazeState, Move, ALL_DIRECTIONS };
+use crate::solve::{ DetachedProblem, SearchProblem, SearchState };
+
+// walk of the fog-of-war agent through the maze
+#[derive(Clone, Debug)]
+pub struct Exploration {
+    pub path: Vec<(usize, usize)>,
+    pub escaped: bool,
+    // cells the agent has seen by the end of the walk
+    pub seen: Array2D<bool>,
+    pub replans: usize,
+    pub elapsed: Duration,
+}
+
+impl Exploration {
+    pub fn steps(&self) -> usize {
+        self.path.len() - 1
+    }
+}
+
+// the real maze, which only shows the agent the cells it stands on or could reach with a single move
+pub struct Simulator<'a> {
+    problem: SearchProblem<'a>,
+    state: SearchState,
+    seen: Array2D<bool>,
+}
+
+impl<'a> Simulator<'a> {
+    pub fn new(maze_table: &'a Array2D<MazeCell>, initial_maze_state: &MazeState) -> Self {
+        let problem = SearchProblem::new(maze_table, initial_maze_state);
+        let state = problem.initial_node().state;
+        let mut simulator = Self {
+            problem,
+            state,
+            seen: Array2D::filled_with(false, maze_table.num_rows(), maze_table.num_columns()),
+        };
+        simulator.reveal();
+        return simulator;
+    }
+
+    pub fn state(&self) -> &SearchState {
+        &self.state
+    }
+
+    pub fn seen(&self) -> &Array2D<bool> {
+        &self.seen
+    }
+
+    pub fn has_escaped(&self) -> bool {
+        self.problem.is_goal(&self.state)
+    }
+
+    // reveals the current cell and its surroundings, returning the ones that weren't seen before
+    fn reveal(&mut self) -> Vec<(usize, usize)> {
+        let maze_table = self.problem.maze_table();
+        let position = self.state.position();
+        let mut visible: Vec<(usize, usize)> = vec![position];
+        for direction in &ALL_DIRECTIONS {
+            visible.extend(neighbour_in_direction(maze_table, position, direction));
+        }
+        // a pad in view shows where it leads, as stepping on it may teleport the agent there in the same move
+        let pad_partners: Vec<(usize, usize)> = visible.iter().filter_map(|visible_position| maze_table[*visible_position].teleport_to).collect();
+        visible.extend(pad_partners);
+        if maze_table[position].stairs_up {
+            visible.extend(cell_on_next_floor(maze_table, position, true));
+        }
+        if maze_table[position].stairs_down {
+            visible.extend(cell_on_next_floor(maze_table, position, false));
+        }
+
+        let mut revealed = Vec::new();
+        for visible_position in visible {
+            if !self.seen[visible_position] {
+                self.seen[visible_position] = true;
+                revealed.push(visible_position);
+            }
+        }
+        return revealed;
+    }
+
+    // makes the move in the real maze, returning the cells that came into view, or None if the move isn't possible
+    pub fn walk(&mut self, next_move: &Move) -> Option<Vec<(usize, usize)>> {
+        self.state = self.problem.successor(0, &self.state, next_move)?.state;
+        return Some(self.reveal());
+    }
+}
+
+// what the agent believes the maze to be: seen cells as they are, and every unseen cell an exit, so that
+// planning optimistically heads for the known exits or the unexplored parts of the maze
+fn believed_maze(maze_table: &Array2D<MazeCell>, initial_maze_state: &MazeState, seen: &Array2D<bool>) -> (Array2D<MazeCell>, MazeState) {
+    let mut believed_table = maze_table.clone();
+    for maze_cell in maze_table.elements_row_major_iter() {
+        let position = (maze_cell.row_index, maze_cell.col_index);
+        if !seen[position] {
+            let mut unseen_cell = MazeCell::new();
+            unseen_cell.row_index = maze_cell.row_index;
+            unseen_cell.col_index = maze_cell.col_index;
+            unseen_cell.level = maze_cell.level;
+            unseen_cell.end_of_maze = true;
+            // unseen hazards never block, but keep their period so ticks count the same as in the real maze
+            unseen_cell.hazard = maze_cell.hazard.as_ref().map(|hazard| Hazard { period: hazard.period, blocked_ticks: Vec::new() });
+            believed_table[position] = unseen_cell;
+        }
+    }
+
+    // keys and doors keep their ids, so the real search state can be planned from, but unseen gates are unknown
+    let mut believed_state = initial_maze_state.clone();
+    believed_state.gates.retain(|(row_index, col_index, _), _| seen[(*row_index, *col_index)]);
+    return (believed_table, believed_state);
+}
+
+// shows a cell that came into view as it really is, together with the gates declared on it
+fn reveal_believed_cell(maze_table: &Array2D<MazeCell>, initial_maze_state: &MazeState, believed_table: &mut Array2D<MazeCell>,
+        believed_problem: &mut DetachedProblem, position: (usize, usize)) {
+    believed_table[position] = maze_table[position].clone();
+    for direction in &ALL_DIRECTIONS {
+        let gate = (position.0, position.1, direction.clone());
+        if let Some(group) = initial_maze_state.gates.get(&gate) {
+            believed_problem.add_gate(believed_table, &gate, *group, initial_maze_state.open_gates.contains(&gate));
+        }
+    }
+}
+
+// shortest list of moves to a goal of the problem, starting from the given state
+fn plan_moves(problem: &SearchProblem, start_state: &SearchState) -> Option<VecDeque<Move>> {
+    let mut visited: HashSet<SearchState> = HashSet::new();
+    visited.insert(start_state.clone());
+
+    // states with the node they were reached from and the move that got there
+    let mut search_nodes: Vec<(SearchState, Option<(usize, Move)>)> = vec![(start_state.clone(), None)];
+    let mut bfs_queue: VecDeque<usize> = VecDeque::new();
+    bfs_queue.push_back(0);
+
+    while let Some(node_index) = bfs_queue.pop_front() {
+        let state = search_nodes[node_index].0.clone();
+        if problem.is_goal(&state) {
+            let mut moves: VecDeque<Move> = VecDeque::new();
+            let mut index = node_index;
+            while let Some((parent_index, next_move)) = &search_nodes[index].1 {
+                moves.push_front(next_move.clone());
+                index = *parent_index;
+            }
+            return Some(moves);
+        }
+
+        for next_move in problem.moves(&state) {
+            let Some(neighbour_node) = problem.successor(node_index, &state, &next_move) else { continue };
+            if visited.insert(neighbour_node.state.clone()) {
+                bfs_queue.push_back(search_nodes.len());
+                search_nodes.push((neighbour_node.state, Some((node_index, next_move))));
+            }
+        }
+    }
+
+    return None;
+}
+
+// walks the maze seeing only the cells next to it, following the shortest plan through the believed maze
+// and replanning whenever new cells come into view, until it escapes or no plan is left
+pub fn explore_maze(maze_table: &Array2D<MazeCell>, initial_maze_state: &MazeState) -> Exploration {
+    let now = Instant::now();
+    let mut simulator = Simulator::new(maze_table, initial_maze_state);
+    // the believed maze is built once, and only the cells that come into view change after that
+    let (mut believed_table, believed_state) = believed_maze(maze_table, initial_maze_state, simulator.seen());
+    let mut believed_problem = SearchProblem::new(&believed_table, &believed_state).detach();
+    let mut path: Vec<(usize, usize)> = vec![simulator.state().position()];
+    let mut planned_moves: VecDeque<Move> = VecDeque::new();
+    let mut replans = 0;
+    let mut needs_plan = true;
+
+    while !simulator.has_escaped() {
+        if needs_plan || planned_moves.is_empty() {
+            let problem = believed_problem.attach(&believed_table);
+            replans += 1;
+            let plan = plan_moves(&problem, simulator.state());
+            believed_problem = problem.detach();
+            match plan {
+                Some(moves) => planned_moves = moves,
+                // every reachable cell is seen and none of them is an exit
+                None => break,
+            }
+        }
+
+        // the next move starts on a seen cell with seen surroundings, so the real maze agrees with the plan
+        let next_move = planned_moves.pop_front().unwrap();
+        let revealed = simulator.walk(&next_move).unwrap();
+        for position in &revealed {
+            reveal_believed_cell(maze_table, initial_maze_state, &mut believed_table, &mut believed_problem, *position);
+        }
+        needs_plan = !revealed.is_empty();
+        path.push(simulator.state().position());
+    }
+
+    return Exploration {
+        path,
+        escaped: simulator.has_escaped(),
+        seen: simulator.seen().clone(),
+        replans,
+        elapsed: now.elapsed(),
+    };
+}
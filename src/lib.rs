@@ -0,0 +1,23 @@
+//! Sequential and parallel BFS solvers for mazes with walls, doors and keys.
+//!
+//! A maze definition is read with [`parse::read_maze_from_file`], checked with
+//! [`validate::validate_maze`], solved with any [`solver::Solver`] from a
+//! [`solver::SolverRegistry`] and printed with the functions in [`render`].
+//! Custom strategies implement [`solver::Solver`] on top of [`solve::SearchProblem`], and
+//! [`explore::explore_maze`] walks a maze that is only revealed as the agent moves. Mazes with
+//! several agents are solved with [`multi_agent::solve_maze_multi_agent`].
+
+#![allow(clippy::needless_return, clippy::upper_case_acronyms)]
+
+pub mod astar;
+pub mod dijkstra;
+pub mod explore;
+pub mod maze;
+pub mod multi_agent;
+pub mod parse;
+pub mod render;
+pub mod solve;
+pub mod solver;
+pub mod validate;
+
+pub use array2d::Array2D;
@@ -1,558 +1,317 @@
 // Milovan Milovanovic, E2-119-2022
 
-use array2d::{Array2D, Error};
-use std::fs::File;
-use std::hash::Hash;
-use std::io::{ self, BufRead, BufReader };
+#![allow(clippy::needless_return)]
+
+use maze_escape_rust::Array2D;
+use maze_escape_rust::explore::explore_maze;
+use maze_escape_rust::maze::{ KeyRule, MazeCell, MazeState };
+use maze_escape_rust::multi_agent::{ solve_maze_multi_agent, Schedule };
+use maze_escape_rust::parse::{ read_maze, read_maze_from_file, MazeParseError };
+use maze_escape_rust::render::{ draw_initial_maze, write_and_draw_exploration, write_and_draw_multi_agent_solution, write_and_draw_solution };
+use maze_escape_rust::solve::default_num_threads;
+use maze_escape_rust::solver::SolverRegistry;
+use maze_escape_rust::validate::{ repair_maze, validate_maze };
+use std::env;
+use std::io;
 use std::process::exit;
-use std::collections::{ HashSet, VecDeque };
-use std::sync::{Arc, Mutex, mpsc};
-use std::thread;
 
-#[derive(Clone, PartialEq, Debug)]
-enum Direction {
-    WEST,
-    EAST,
-    NORTH,
-    SOUTH
+const EXIT_SOLVED: i32 = 0;
+const EXIT_UNSOLVABLE: i32 = 1;
+const EXIT_INVALID_INPUT: i32 = 2;
+const EXIT_IO_ERROR: i32 = 3;
+
+const USAGE: &str = "Usage: maze-escape-rust <command> <file> [options]
+
+Commands:
+    solve <file>       solve the maze and print the solution
+    render <file>      draw the initial maze
+    validate <file>    check walls, doors, keys and exits
+    bench <file>       time every solver on the maze
+    explore <file>     walk the maze seeing only the cells next to the walker, next to the BFS optimum
+
+Use - as <file> to read the maze definition from stdin.
+
+Options:
+    --algo <name>      solver used by solve (default: sequential), one of: {solvers}
+    --threads <n>      worker threads used by the parallel solvers (default: number of CPUs)
+    --repair           make walls and doors symmetric before solving
+    --unlock-cost <n>  cost of unlocking a door, overrides the maze header rule \"unlock\"
+    --pickup-cost <n>  cost of picking up a key, overrides the maze header rule \"pickup\"
+    --keys <rule>      consumed, reusable or the number of doors a key opens, overrides the maze header rule \"keys\"
+    --capacity <n>     most keys carried at once, overrides the maze header rule \"capacity\"
+    --schedule <name>  joint or turns, how the agents of a maze with several agents move, used instead of --algo (default: joint)
+
+Exit codes: 0 = solved / valid, 1 = unsolvable, 2 = invalid input, 3 = I/O error";
+
+fn usage() -> String {
+    let registry = SolverRegistry::with_builtin_solvers(1);
+    return USAGE.replace("{solvers}", &registry.names().join(", "));
 }
 
-#[derive(Clone)]
-struct MazeCell {
-    row_index: usize,
-    col_index: usize,
-    available_directions: Vec<Direction>,
-    end_of_maze: bool
+#[derive(PartialEq)]
+enum Command {
+    Solve,
+    Render,
+    Validate,
+    Bench,
+    Explore
 }
 
-impl MazeCell {
-    fn new () -> Self {
-        Self {
-            row_index: 0,
-            col_index: 0,
-            available_directions: Vec::new(),
-            end_of_maze: false
-        }
-    }
+struct CliOptions {
+    command: Command,
+    filename: String,
+    algorithm: String,
+    num_threads: usize,
+    repair: bool,
+    unlock_cost: Option<u32>,
+    pickup_cost: Option<u32>,
+    key_rule: Option<KeyRule>,
+    key_capacity: Option<u32>,
+    schedule: Schedule
 }
 
-#[derive(Clone)]
-struct MazeState {
-    current_position: (usize, usize),
-    previous_positions: Vec<(usize, usize)>,
-    cells_with_locked_doors: Vec<(usize, usize, Direction)>,
-    keys_left: Vec<(usize, usize)>,
-    num_keys_to_use: u32,
-}
-
-impl MazeState {
-    fn new() -> Self {
-        Self {
-            current_position: (0, 0),
-            previous_positions: Vec::new(),
-            cells_with_locked_doors: Vec::new(),
-            keys_left: Vec::new(),
-            num_keys_to_use: 0
-        }
-    }
-}
-
-fn read_maze_from_file(filename: String) -> (Array2D<MazeCell>, MazeState) {
-    let file = File::open(&filename);
-    match file {
-        Ok(file) => {
-            let lines = io::BufReader::new(file).lines();
-            let mut maze_table = Array2D::<MazeCell>::filled_with(MazeCell::new(), 6, 9);
-            let mut maze_state = MazeState::new();
-            // let mut iter = 0;
-            let mut row_iter = 0;
-            let mut col_iter = 0;
-            for line in lines {
-                match line {
-                    Ok(line) => {
-                        let line_vec: Vec<char> = line.chars().collect();
-                        // let mut maze_cell = maze_table.get_mut_row_major(iter).unwrap();
-                        let mut maze_cell = maze_table.get_mut(row_iter, col_iter).unwrap();
-
-                        maze_cell.row_index = row_iter;
-                        maze_cell.col_index = col_iter;
-
-                        if line_vec[0] == '1' {
-                            let direction = Direction::WEST;
-                            maze_cell.available_directions.push(direction);
-                        }
-                        if line_vec[1] == '1' {
-                            let direction = Direction::EAST;
-                            maze_cell.available_directions.push(direction);
-                        }
-                        if line_vec[2] == '1' {
-                            let direction = Direction::NORTH;
-                            maze_cell.available_directions.push(direction);
-                        }
-                        if line_vec[3] == '1' {
-                            let direction = Direction::SOUTH;
-                            maze_cell.available_directions.push(direction);
-                        }
-
-                        if line_vec[5] == '1' {
-                            let direction = Direction::WEST;
-                            maze_state.cells_with_locked_doors.push((row_iter, col_iter, direction));
-                        }
-                        if line_vec[6] == '1' {
-                            let direction = Direction::EAST;
-                            maze_state.cells_with_locked_doors.push((row_iter, col_iter, direction));
-                        }
-                        if line_vec[7] == '1' {
-                            let direction = Direction::NORTH;
-                            maze_state.cells_with_locked_doors.push((row_iter, col_iter, direction));
-                        }
-                        if line_vec[8] == '1' {
-                            let direction = Direction::SOUTH;
-                            maze_state.cells_with_locked_doors.push((row_iter, col_iter, direction));
-                        }
-
-                        if line_vec[10] == '1' && line_vec[11] == '1' {
-                            maze_state.keys_left.push((row_iter, col_iter));
-                        }
-
-                        if line_vec[12] == '1' && line_vec[13] == '1' {
-                            maze_cell.end_of_maze = true;
-                        }
-                    },
-                    Err(_) => {
-                        println!("Couldn't read line");
-                        exit(1);
-                    }
-                }
-
-                col_iter += 1;
-                if col_iter >= 9 {
-                    row_iter += 1;
-                    col_iter = 0;
-                }
-            }
-            
-            return (maze_table, maze_state);
-        },
-        Err(_) => {
-            println!("Couldn't read file {}", &filename);
-            exit(1);
-        }
-    }
-}
-
-fn get_valid_neighbours(maze_table: &Array2D<MazeCell>, maze_state: &MazeState) -> Vec<MazeState> {
-    let mut valid_neighbours: Vec<MazeState> = Vec::new();
-
-    let current_cell = maze_table.get(maze_state.current_position.0, maze_state.current_position.1).unwrap();
-    for direction in &current_cell.available_directions {
-        let mut neighbour_position: (usize, usize);
-        let opposite_direction: Direction;
-        match *direction {
-            Direction::WEST => {
-                neighbour_position = (current_cell.row_index, current_cell.col_index - 1);
-                opposite_direction = Direction::EAST;
+fn parse_args(args: &[String]) -> Result<CliOptions, String> {
+    let command = match args.first().map(|arg| arg.as_str()) {
+        Some("solve") => Command::Solve,
+        Some("render") => Command::Render,
+        Some("validate") => Command::Validate,
+        Some("bench") => Command::Bench,
+        Some("explore") => Command::Explore,
+        Some(other) => return Err(format!("unknown command \"{}\"", other)),
+        None => return Err(String::from("missing command")),
+    };
+
+    let mut options = CliOptions {
+        command,
+        filename: String::new(),
+        algorithm: String::from("sequential"),
+        num_threads: default_num_threads(),
+        repair: false,
+        unlock_cost: None,
+        pickup_cost: None,
+        key_rule: None,
+        key_capacity: None,
+        schedule: Schedule::Joint
+    };
+    let mut filename: Option<String> = None;
+
+    let mut iter = args[1..].iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--algo" => match iter.next() {
+                Some(algorithm) => options.algorithm = algorithm.clone(),
+                None => return Err(String::from("--algo needs a value")),
             },
-            Direction::EAST => { 
-                neighbour_position = (current_cell.row_index, current_cell.col_index + 1);
-                opposite_direction = Direction::WEST;
+            "--threads" => match iter.next().map(|value| value.parse::<usize>()) {
+                Some(Ok(num_threads)) if num_threads > 0 => options.num_threads = num_threads,
+                Some(_) => return Err(String::from("--threads needs a positive number")),
+                None => return Err(String::from("--threads needs a value")),
             },
-            Direction::NORTH => { 
-                neighbour_position = (current_cell.row_index - 1, current_cell.col_index);
-                opposite_direction = Direction::SOUTH;
+            "--repair" => options.repair = true,
+            "--unlock-cost" => match iter.next().map(|value| value.parse::<u32>()) {
+                Some(Ok(cost)) => options.unlock_cost = Some(cost),
+                Some(_) => return Err(String::from("--unlock-cost needs a non-negative number")),
+                None => return Err(String::from("--unlock-cost needs a value")),
             },
-            Direction::SOUTH => { 
-                neighbour_position = (current_cell.row_index + 1, current_cell.col_index);
-                opposite_direction = Direction::NORTH;
+            "--pickup-cost" => match iter.next().map(|value| value.parse::<u32>()) {
+                Some(Ok(cost)) => options.pickup_cost = Some(cost),
+                Some(_) => return Err(String::from("--pickup-cost needs a non-negative number")),
+                None => return Err(String::from("--pickup-cost needs a value")),
             },
-        }
-
-        let mut neighbour_state = MazeState::new();
-        neighbour_state.current_position = neighbour_position;
-        neighbour_state.cells_with_locked_doors = maze_state.cells_with_locked_doors.clone();
-        neighbour_state.num_keys_to_use = maze_state.num_keys_to_use;
-
-        // check door and unlock (from both sides) if needed
-        if maze_state.cells_with_locked_doors.contains(&(maze_state.current_position.0, maze_state.current_position.1, direction.clone())) {
-            // decrement num of keys and unlock door for next state
-            if maze_state.num_keys_to_use > 0 {
-                neighbour_state.num_keys_to_use -= 1;
-                let current_cell_with_locked_door = (maze_state.current_position.0, maze_state.current_position.1, direction.clone());
-                let neighbour_cell_with_locked_door = (neighbour_position.0, neighbour_position.1, opposite_direction);
-                neighbour_state.cells_with_locked_doors.retain(|x| *x != current_cell_with_locked_door && *x != neighbour_cell_with_locked_door);
-            }
-            // no available keys, so neighbour is not valid
-            else {
-                continue;
-            }
-        }
-
-        neighbour_state.keys_left = maze_state.keys_left.clone();
-
-        // pick up key in neighbour cell if available
-        if maze_state.keys_left.contains(&neighbour_position) {
-            neighbour_state.num_keys_to_use += 1;
-            neighbour_state.keys_left.retain(|x| *x != neighbour_position);
-        }
-
-        neighbour_state.previous_positions = maze_state.previous_positions.clone();
-        neighbour_state.previous_positions.push(maze_state.current_position);
-
-        valid_neighbours.push(neighbour_state);
-    }
-
-    return valid_neighbours;
-}
-
-fn solve_maze_bfs(maze_table: Array2D<MazeCell>, initial_maze_state: MazeState) {
-    // cells visited while having visited[2] keys available
-    let mut visited: HashSet<(usize, usize, u32)> = HashSet::new();
-    visited.insert((initial_maze_state.current_position.0, initial_maze_state.current_position.1, initial_maze_state.num_keys_to_use));
-
-    let mut bfs_queue: VecDeque<MazeState> = VecDeque::new();
-    bfs_queue.push_back(initial_maze_state);
-
-    let mut maze_end_state: Option<MazeState> = None;
-
-    while !bfs_queue.is_empty() {
-        let current_maze_state = bfs_queue.pop_front().unwrap();
-        let current_maze_cell = maze_table.get(current_maze_state.current_position.0, current_maze_state.current_position.1).unwrap();
-
-        // found end of maze
-        if current_maze_cell.end_of_maze {
-            maze_end_state = Some(current_maze_state);
-            break;
-        }
-
-        for neighbour_state in get_valid_neighbours(&maze_table, &current_maze_state) {
-            // ignore neighbour if it has been visited with the same number of keys available
-            if !visited.contains(&(neighbour_state.current_position.0, neighbour_state.current_position.1, neighbour_state.num_keys_to_use)) {
-                visited.insert((neighbour_state.current_position.0, neighbour_state.current_position.1, neighbour_state.num_keys_to_use));
-                bfs_queue.push_back(neighbour_state);
-            }
+            "--keys" => match iter.next().map(|value| value.parse::<KeyRule>()) {
+                Some(Ok(key_rule)) => options.key_rule = Some(key_rule),
+                Some(_) => return Err(String::from("--keys needs \"consumed\", \"reusable\" or a positive number")),
+                None => return Err(String::from("--keys needs a value")),
+            },
+            "--capacity" => match iter.next().map(|value| value.parse::<u32>()) {
+                Some(Ok(capacity)) if capacity > 0 => options.key_capacity = Some(capacity),
+                Some(_) => return Err(String::from("--capacity needs a positive number")),
+                None => return Err(String::from("--capacity needs a value")),
+            },
+            "--schedule" => match iter.next().map(|value| value.parse::<Schedule>()) {
+                Some(Ok(schedule)) => options.schedule = schedule,
+                Some(_) => return Err(String::from("--schedule needs \"joint\" or \"turns\"")),
+                None => return Err(String::from("--schedule needs a value")),
+            },
+            "-" => filename = Some(arg.clone()),
+            _ if arg.starts_with('-') => return Err(format!("unknown option \"{}\"", arg)),
+            _ if filename.is_none() => filename = Some(arg.clone()),
+            _ => return Err(format!("unexpected argument \"{}\"", arg)),
         }
     }
 
-    match maze_end_state {
-        Some(maze_end_state) => {
-            // form & draw solution output
-            write_and_draw_solution(&maze_end_state, &maze_table, String::from("sequential"));
-        },
-        None => return
+    match filename {
+        Some(filename) => options.filename = filename,
+        None => return Err(String::from("missing maze definition file")),
     }
+    return Ok(options);
 }
 
-fn get_new_state_if_neighbour_valid(maze_state: &MazeState, current_cell: &MazeCell, direction: Direction) -> Option<MazeState> {
-    let neighbour_position: (usize, usize);
-    let opposite_direction: Direction;
-    match direction {
-        Direction::WEST => {
-            neighbour_position = (current_cell.row_index, current_cell.col_index - 1);
-            opposite_direction = Direction::EAST;
-        },
-        Direction::EAST => { 
-            neighbour_position = (current_cell.row_index, current_cell.col_index + 1);
-            opposite_direction = Direction::WEST;
-        },
-        Direction::NORTH => { 
-            neighbour_position = (current_cell.row_index - 1, current_cell.col_index);
-            opposite_direction = Direction::SOUTH;
-        },
-        Direction::SOUTH => { 
-            neighbour_position = (current_cell.row_index + 1, current_cell.col_index);
-            opposite_direction = Direction::NORTH;
-        },
+// reads, optionally repairs and validates the maze, or returns the exit code to stop with
+fn load_maze(options: &CliOptions) -> Result<(Array2D<MazeCell>, MazeState), i32> {
+    let maze = if options.filename == "-" {
+        read_maze(io::stdin().lock())
     }
-
-    let mut neighbour_state = MazeState::new();
-    neighbour_state.current_position = neighbour_position;
-    neighbour_state.cells_with_locked_doors = maze_state.cells_with_locked_doors.clone();
-    neighbour_state.num_keys_to_use = maze_state.num_keys_to_use;
-
-    // check door and unlock (from both sides) if needed
-    if maze_state.cells_with_locked_doors.contains(&(maze_state.current_position.0, maze_state.current_position.1, direction.clone())) {
-        // decrement num of keys and unlock door for next state
-        if maze_state.num_keys_to_use > 0 {
-            neighbour_state.num_keys_to_use -= 1;
-            let current_cell_with_locked_door = (maze_state.current_position.0, maze_state.current_position.1, direction.clone());
-            let neighbour_cell_with_locked_door = (neighbour_position.0, neighbour_position.1, opposite_direction);
-            neighbour_state.cells_with_locked_doors.retain(|x| *x != current_cell_with_locked_door && *x != neighbour_cell_with_locked_door);
-        }
-        // no available keys, so neighbour is not valid
-        else {
-            return None;
+    else {
+        read_maze_from_file(options.filename.clone())
+    };
+
+    let (mut maze_table, mut initial_maze_state) = match maze {
+        Ok(maze) => maze,
+        Err(MazeParseError::Io(error)) => {
+            eprintln!("Couldn't read {}: {}", options.filename, error);
+            return Err(EXIT_IO_ERROR);
+        },
+        Err(error) => {
+            eprintln!("Invalid maze definition {}: {}", options.filename, error);
+            return Err(EXIT_INVALID_INPUT);
         }
-    }
-
-    neighbour_state.keys_left = maze_state.keys_left.clone();
+    };
 
-    // pick up key in neighbour cell if available
-    if maze_state.keys_left.contains(&neighbour_position) {
-        neighbour_state.num_keys_to_use += 1;
-        neighbour_state.keys_left.retain(|x| *x != neighbour_position);
+    if let Some(cost) = options.unlock_cost {
+        initial_maze_state.action_costs.unlock = cost;
     }
-
-    neighbour_state.previous_positions = maze_state.previous_positions.clone();
-    neighbour_state.previous_positions.push(maze_state.current_position);
-
-    return Some(neighbour_state);
-}
-
-fn solve_maze_bfs_parallel(maze_table: Array2D<MazeCell>, initial_maze_state: MazeState) {
-    // cells visited while having visited[2] keys available
-    let visited: Arc<Mutex<HashSet<(usize, usize, u32)>>> = Arc::new(Mutex::new(HashSet::new()));
-    {
-        visited.lock().unwrap().insert((initial_maze_state.current_position.0, initial_maze_state.current_position.1, initial_maze_state.num_keys_to_use));
+    if let Some(cost) = options.pickup_cost {
+        initial_maze_state.action_costs.pickup = cost;
     }
-
-    let bfs_queue: Arc<Mutex<VecDeque<MazeState>>> = Arc::new(Mutex::new(VecDeque::new()));
-    {
-        bfs_queue.lock().unwrap().push_back(initial_maze_state);
-    }
-
-    let mut maze_end_state: Option<MazeState> = None;
-
-    loop {
-        {
-            if bfs_queue.lock().unwrap().is_empty() {
-                break;
-            }
-        }
-        let current_maze_state: Arc<MazeState>;
-        {
-            current_maze_state = Arc::new(bfs_queue.lock().unwrap().pop_front().unwrap());
-        }
-        
-        let current_position = current_maze_state.current_position;
-        let current_maze_cell = Arc::new(maze_table.get(current_position.0, current_position.1).unwrap().clone());
-
-        // found end of maze
-        if current_maze_cell.end_of_maze {
-            match Arc::try_unwrap(current_maze_state) {
-                Ok(current_maze_state) => maze_end_state = Some(current_maze_state),
-                Err(_) => maze_end_state = None,
-            }
-            break;
-        }
-
-        let mut spawned_threads = Vec::new();
-           
-        for direction in current_maze_cell.available_directions.clone() {
-            let visited = Arc::clone(&visited);
-            let bfs_queue = Arc::clone(&bfs_queue);
-            let current_maze_state = Arc::clone(&current_maze_state);
-            let current_maze_cell = Arc::clone(&current_maze_cell);
-            let thread = thread::spawn(move || {
-                let neighbour_state = get_new_state_if_neighbour_valid(&current_maze_state, &current_maze_cell, direction.clone());
-
-                match neighbour_state {
-                    Some(neighbour_state) => {
-                        let mut visited_guard = visited.lock().unwrap();
-                        // ignore neighbour if it has been visited with the same number of keys available
-                        if !visited_guard.contains(&(neighbour_state.current_position.0, neighbour_state.current_position.1, neighbour_state.num_keys_to_use)) {
-                            visited_guard.insert((neighbour_state.current_position.0, neighbour_state.current_position.1, neighbour_state.num_keys_to_use));
-                            bfs_queue.lock().unwrap().push_back(neighbour_state);
-                        }
-                    },
-                    None => (),
-                }
-            });
-            spawned_threads.push(thread);
-        }
-
-        for thread in spawned_threads {
-            thread.join().unwrap();
-        }
+    if let Some(key_rule) = options.key_rule {
+        initial_maze_state.key_rule = key_rule;
     }
-
-    match maze_end_state {
-        Some(maze_end_state) => {
-            // form & draw solution output
-            write_and_draw_solution(&maze_end_state, &maze_table, String::from("parallel"));
-        },
-        None => return
+    if let Some(capacity) = options.key_capacity {
+        initial_maze_state.key_capacity = Some(capacity);
     }
-}
-
-fn solve_maze_bfs_parallel_channels(maze_table: Array2D<MazeCell>, initial_maze_state: MazeState) {
-    // cells visited while having visited[2] keys available
-    let mut visited: HashSet<(usize, usize, u32)> = HashSet::new();
-    visited.insert((initial_maze_state.current_position.0, initial_maze_state.current_position.1, initial_maze_state.num_keys_to_use));
-
-    let mut bfs_queue: VecDeque<MazeState> = VecDeque::new();
-    bfs_queue.push_back(initial_maze_state);
-
-    let (tx, rx) = mpsc::channel();
 
-    let mut maze_end_state: Option<MazeState> = None;
-
-    while !bfs_queue.is_empty() {
-        let current_maze_state = Arc::new(bfs_queue.pop_front().unwrap());
-        
-        let current_position = current_maze_state.current_position;
-        let current_maze_cell = Arc::new(maze_table.get(current_position.0, current_position.1).unwrap().clone());
-
-        // found end of maze
-        if current_maze_cell.end_of_maze {
-            match Arc::try_unwrap(current_maze_state) {
-                Ok(current_maze_state) => maze_end_state = Some(current_maze_state),
-                Err(_) => maze_end_state = None,
-            }
-            break;
-        }
-
-        let mut spawned_threads = Vec::new();
-           
-        for direction in current_maze_cell.available_directions.clone() {
-            let current_maze_state = Arc::clone(&current_maze_state);
-            let current_maze_cell = Arc::clone(&current_maze_cell);
-            let tx = tx.clone();
-            let thread = thread::spawn(move || {
-                let neighbour_state = get_new_state_if_neighbour_valid(&current_maze_state, &current_maze_cell, direction.clone());
-
-                match neighbour_state {
-                    Some(neighbour_state) => {
-                        tx.send(neighbour_state).unwrap();
-                    },
-                    None => (),
-                }
-            });
-            spawned_threads.push(thread);
+    if options.repair {
+        for issue in repair_maze(&mut maze_table, &mut initial_maze_state) {
+            eprintln!("repaired: {}", issue.display(&maze_table));
         }
+    }
 
-        for thread in spawned_threads {
-            thread.join().unwrap();
+    let issues = validate_maze(&maze_table, &initial_maze_state);
+    for issue in &issues {
+        if issue.is_error() {
+            eprintln!("error: {}", issue.display(&maze_table));
         }
-
-        for received_neighbour_states in rx.try_iter() {
-            if !visited.contains(&(received_neighbour_states.current_position.0, received_neighbour_states.current_position.1, received_neighbour_states.num_keys_to_use)) {
-                visited.insert((received_neighbour_states.current_position.0, received_neighbour_states.current_position.1, received_neighbour_states.num_keys_to_use));
-                bfs_queue.push_back(received_neighbour_states);
-            }
+        else {
+            eprintln!("warning: {}", issue.display(&maze_table));
         }
     }
-
-    match maze_end_state {
-        Some(maze_end_state) => {
-            // form & draw solution output
-            write_and_draw_solution(&maze_end_state, &maze_table, String::from("parallel"));
-        },
-        None => return
+    if issues.iter().any(|issue| issue.is_error()) {
+        eprintln!("Maze definition {} is inconsistent, run with --repair to fix walls and doors", options.filename);
+        return Err(EXIT_INVALID_INPUT);
     }
+
+    return Ok((maze_table, initial_maze_state));
 }
 
-fn write_and_draw_solution(maze_end_state: &MazeState, maze_table: &Array2D<MazeCell>, keyword: String) {
-    println!("(row, col) indexes of {} solution in order:\n", keyword);
-    let mut iter = 1;
-    for position in &maze_end_state.previous_positions {
-        println!("{}. ({}, {})", iter, position.0, position.1);
-        iter += 1
+fn run(options: &CliOptions) -> i32 {
+    let registry = SolverRegistry::with_builtin_solvers(options.num_threads);
+    if registry.get(&options.algorithm).is_none() {
+        eprintln!("error: unknown algorithm \"{}\", expected one of: {}", options.algorithm, registry.names().join(", "));
+        return EXIT_INVALID_INPUT;
     }
-    println!("{}. ({}, {})", iter, maze_end_state.current_position.0, maze_end_state.current_position.1);
 
-    println!("\nEnd of {} solution.\n\nTable representation of solution (0 = untraversed; 1 = traversed):\n", keyword);
-    for iterator in maze_table.rows_iter() {
-        for maze_cell in iterator {
-            if maze_end_state.previous_positions.contains(&(maze_cell.row_index, maze_cell.col_index)) ||
-                maze_end_state.current_position == (maze_cell.row_index, maze_cell.col_index) {
-                    print!("1  ");
-                }
-            else {
-                print!("0  ");
-            }
-        }
-        println!("\n");
-    }
-}
-
-fn draw_initial_maze(initial_maze_state: &MazeState, maze_table: &Array2D<MazeCell>) {
-    println!("\nTable representation of initial maze:\n");
-    for iterator in maze_table.rows_iter() {
-        for maze_cell in iterator {
-            let mut cell_num = 0;
-            if initial_maze_state.keys_left.contains(&(maze_cell.row_index, maze_cell.col_index)) {
-                cell_num = 1;
-            }
-            if maze_cell.end_of_maze {
-                cell_num = 2;
-            }
+    let (maze_table, initial_maze_state) = match load_maze(options) {
+        Ok(maze) => maze,
+        Err(exit_code) => return exit_code,
+    };
 
-            if maze_cell.available_directions.contains(&Direction::WEST) {
-                if initial_maze_state.cells_with_locked_doors.contains(&(maze_cell.row_index, maze_cell.col_index, Direction::WEST)) {
-                    print!("D<-");
-                }
-                else {
-                    print!("<-");
+    let multi_agent = !initial_maze_state.other_agents.is_empty();
+    match options.command {
+        Command::Validate => {
+            println!("Maze definition {} is valid.", options.filename);
+            return EXIT_SOLVED;
+        },
+        Command::Render => {
+            draw_initial_maze(&initial_maze_state, &maze_table);
+            return EXIT_SOLVED;
+        },
+        Command::Solve if multi_agent => {
+            match solve_maze_multi_agent(&maze_table, &initial_maze_state, options.schedule) {
+                Ok(solution) => {
+                    write_and_draw_multi_agent_solution(&solution, &maze_table);
+                    println!("Elapsed ({}): {:.2?}", options.schedule.name(), solution.elapsed);
+                    return EXIT_SOLVED;
+                },
+                Err(unsolvable) => {
+                    println!("No {} solution found after expanding {} states.", options.schedule.name(), unsolvable.states_expanded);
+                    return EXIT_UNSOLVABLE;
                 }
             }
-            if maze_cell.available_directions.contains(&Direction::NORTH) {
-                if initial_maze_state.cells_with_locked_doors.contains(&(maze_cell.row_index, maze_cell.col_index, Direction::NORTH)) {
-                    print!("D↑");
-                }
-                else {
-                    print!("↑");
+        },
+        Command::Solve => {
+            let solver = registry.get(&options.algorithm).unwrap();
+            match solver.solve(&maze_table, &initial_maze_state) {
+                Ok(solution) => {
+                    // form & draw solution output
+                    write_and_draw_solution(&solution, &maze_table, String::from(solver.name()));
+                    println!("Elapsed ({}): {:.2?}", solver.name(), solution.elapsed);
+                    return EXIT_SOLVED;
+                },
+                Err(unsolvable) => {
+                    println!("No {} solution found after expanding {} states.", solver.name(), unsolvable.states_expanded);
+                    return EXIT_UNSOLVABLE;
                 }
             }
-
-            print!("{}", cell_num);
-
-            if maze_cell.available_directions.contains(&Direction::SOUTH) {
-                if initial_maze_state.cells_with_locked_doors.contains(&(maze_cell.row_index, maze_cell.col_index, Direction::SOUTH)) {
-                    print!("↓D");
-                }
-                else {
-                    print!("↓");
+        },
+        Command::Bench if multi_agent => {
+            let mut exit_code = EXIT_SOLVED;
+            for schedule in [Schedule::Joint, Schedule::TurnBased] {
+                match solve_maze_multi_agent(&maze_table, &initial_maze_state, schedule) {
+                    Ok(solution) => println!("{:<14} {:>10.2?}  makespan {}, {} moves, {} states expanded",
+                        schedule.name(), solution.elapsed, solution.makespan(), solution.total_moves(), solution.states_expanded),
+                    Err(unsolvable) => {
+                        println!("{:<14} {:>10.2?}  no solution, {} states expanded",
+                            schedule.name(), unsolvable.elapsed, unsolvable.states_expanded);
+                        exit_code = EXIT_UNSOLVABLE;
+                    }
                 }
             }
-            if maze_cell.available_directions.contains(&Direction::EAST) {
-                if initial_maze_state.cells_with_locked_doors.contains(&(maze_cell.row_index, maze_cell.col_index, Direction::EAST)) {
-                    print!("->D");
-                }
-                else {
-                    print!("->");
+            return exit_code;
+        },
+        Command::Bench => {
+            let mut exit_code = EXIT_SOLVED;
+            for solver in registry.iter() {
+                match solver.solve(&maze_table, &initial_maze_state) {
+                    Ok(solution) => println!("{:<14} {:>10.2?}  {} moves, cost {}, {} states expanded",
+                        solver.name(), solution.elapsed, solution.path.len() - 1, solution.cost, solution.states_expanded),
+                    Err(unsolvable) => {
+                        println!("{:<14} {:>10.2?}  no solution, {} states expanded",
+                            solver.name(), unsolvable.elapsed, unsolvable.states_expanded);
+                        exit_code = EXIT_UNSOLVABLE;
+                    }
                 }
             }
-
-            print!("  ");
+            return exit_code;
+        },
+        Command::Explore if multi_agent => {
+            eprintln!("error: explore only supports mazes with a single agent");
+            return EXIT_INVALID_INPUT;
+        },
+        Command::Explore => {
+            let exploration = explore_maze(&maze_table, &initial_maze_state);
+            let optimum = registry.get("sequential").unwrap().solve(&maze_table, &initial_maze_state).ok().map(|solution| solution.path.len() - 1);
+            write_and_draw_exploration(&exploration, &maze_table, optimum);
+            println!("Elapsed (explorer): {:.2?}", exploration.elapsed);
+            return if exploration.escaped { EXIT_SOLVED } else { EXIT_UNSOLVABLE };
         }
-        println!("\n");
     }
 }
 
 fn main() {
-    let (maze_table, initial_maze_state) = read_maze_from_file(String::from("maze_def.txt"));
-    let maze_table_parallel = maze_table.clone();
-    let initial_maze_state_parallel = initial_maze_state.clone();
-    let maze_table_parallel_channels = maze_table.clone();
-    let initial_maze_state_parallel_channels = initial_maze_state.clone();
-
-    // for locked in &initial_maze_state.cells_with_locked_doors {
-    //     println!("({}, {}, {:?})", locked.0, locked.1, locked.2);
-    // }
-
-    draw_initial_maze(&initial_maze_state, &maze_table);
-
-    use std::time::Instant;
-    let now = Instant::now();
-
-    solve_maze_bfs(maze_table, initial_maze_state);
-
-    let elapsed = now.elapsed();
-    println!("Elapsed (sequential): {:.2?}", elapsed);
-
-
-    let now = Instant::now();
-
-    solve_maze_bfs_parallel(maze_table_parallel, initial_maze_state_parallel);
-
-    let elapsed = now.elapsed();
-    println!("Elapsed (parallel): {:.2?}", elapsed);
-
-
-    let now = Instant::now();
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", usage());
+        return;
+    }
 
-    solve_maze_bfs_parallel_channels(maze_table_parallel_channels, initial_maze_state_parallel_channels);
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, usage());
+            exit(EXIT_INVALID_INPUT);
+        }
+    };
 
-    let elapsed = now.elapsed();
-    println!("Elapsed (parallel channels): {:.2?}", elapsed);
+    exit(run(&options));
 }
@@ -0,0 +1,400 @@
+use array2d::Array2D;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Direction {
+    WEST,
+    EAST,
+    NORTH,
+    SOUTH
+}
+
+// a single action of the player
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Move {
+    Step(Direction),
+    // jump from a teleporter pad to its partner
+    Teleport,
+    // take the stairs to the same cell one floor up or down
+    Up,
+    Down,
+    // pull the lever the player stands on
+    Pull,
+    // stand still for a tick, e.g. until a hazard clears
+    Wait,
+    // put down a carried key of the color with the given door openings left, or take a key of the color lying
+    // on the cell, only with a key capacity
+    DropKey(usize, u32),
+    PickUpKey(usize),
+}
+
+pub const ALL_DIRECTIONS: [Direction; 4] = [Direction::WEST, Direction::EAST, Direction::NORTH, Direction::SOUTH];
+
+impl Move {
+    // letter of the move in the move string of a solution
+    pub fn letter(&self) -> char {
+        match self {
+            Move::Step(Direction::WEST) => 'W',
+            Move::Step(Direction::EAST) => 'E',
+            Move::Step(Direction::NORTH) => 'N',
+            Move::Step(Direction::SOUTH) => 'S',
+            Move::Teleport => 'T',
+            Move::Up => 'U',
+            Move::Down => 'D',
+            Move::Pull => 'P',
+            Move::Wait => '.',
+            Move::DropKey(..) => 'X',
+            Move::PickUpKey(_) => 'K',
+        }
+    }
+}
+
+impl Direction {
+    // position of the direction in ALL_DIRECTIONS
+    pub fn index(&self) -> usize {
+        match self {
+            Direction::WEST => 0,
+            Direction::EAST => 1,
+            Direction::NORTH => 2,
+            Direction::SOUTH => 3,
+        }
+    }
+
+    pub fn opposite(&self) -> Direction {
+        match self {
+            Direction::WEST => Direction::EAST,
+            Direction::EAST => Direction::WEST,
+            Direction::NORTH => Direction::SOUTH,
+            Direction::SOUTH => Direction::NORTH,
+        }
+    }
+}
+
+// switch toggling every gate of a group, indexed like MazeState::gate_names
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Switch {
+    // toggles whenever the player steps on the cell
+    Plate(usize),
+    // toggles when the player pulls it, which takes a move
+    Lever(usize),
+}
+
+impl Switch {
+    pub fn group(&self) -> usize {
+        match self {
+            Switch::Plate(group) | Switch::Lever(group) => *group,
+        }
+    }
+}
+
+// cell blocked on some ticks of every period, like a spike trap or a cell a guard patrols
+#[derive(Clone, PartialEq, Debug)]
+pub struct Hazard {
+    pub period: u32,
+    pub blocked_ticks: Vec<u32>,
+}
+
+impl Hazard {
+    pub fn is_blocked(&self, tick: u64) -> bool {
+        return self.blocked_ticks.contains(&((tick % self.period as u64) as u32));
+    }
+}
+
+impl FromStr for Hazard {
+    type Err = ();
+
+    // "<period>:<ticks>", e.g. "4:0,1" for a cell blocked when the tick mod 4 is 0 or 1
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (period, ticks) = value.split_once(':').ok_or(())?;
+        let period = period.parse::<u32>().map_err(|_| ())?;
+        let mut blocked_ticks = Vec::new();
+        for tick in ticks.split(',') {
+            match tick.parse::<u32>() {
+                Ok(tick) if tick < period => blocked_ticks.push(tick),
+                _ => return Err(()),
+            }
+        }
+        return Ok(Hazard { period, blocked_ticks });
+    }
+}
+
+// cell of the maze on its own floor, with the ground floor at level 0
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Position {
+    pub level: usize,
+    pub row: usize,
+    pub col: usize,
+}
+
+// floors are stacked in the maze table, so row_index counts the rows of all floors below as well
+#[derive(Clone)]
+pub struct MazeCell {
+    pub row_index: usize,
+    pub col_index: usize,
+    // the floor the cell is on, which is the only place the maze table keeps its floors, so a table with several
+    // floors that isn't read with read_maze has to fill it in for neighbours, stairs and positions to work
+    pub level: usize,
+    pub available_directions: Vec<Direction>,
+    pub end_of_maze: bool,
+    // cost of moving into the cell
+    pub cost: u32,
+    // passages that can't be walked back, so the neighbour doesn't open towards this cell
+    pub one_way: Vec<Direction>,
+    // partner pad of a teleporter pad
+    pub teleport_to: Option<(usize, usize)>,
+    pub stairs_up: bool,
+    pub stairs_down: bool,
+    pub switch: Option<Switch>,
+    pub hazard: Option<Hazard>
+}
+
+impl MazeCell {
+    pub fn new () -> Self {
+        Self {
+            row_index: 0,
+            col_index: 0,
+            level: 0,
+            available_directions: Vec::new(),
+            end_of_maze: false,
+            cost: 1,
+            one_way: Vec::new(),
+            teleport_to: None,
+            stairs_up: false,
+            stairs_down: false,
+            switch: None,
+            hazard: None
+        }
+    }
+}
+
+impl MazeCell {
+    // whether a hazard keeps the player off the cell at the tick
+    pub fn is_blocked(&self, tick: u64) -> bool {
+        return self.hazard.as_ref().is_some_and(|hazard| hazard.is_blocked(tick));
+    }
+}
+
+impl Default for MazeCell {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub const DEFAULT_COLOR: &str = "default";
+
+// extra cost of the actions taken on top of a move, free unless the maze header says otherwise
+#[derive(Clone, Copy, Default, Debug)]
+pub struct ActionCosts {
+    pub unlock: u32,
+    pub pickup: u32,
+}
+
+// how often a key can open a door before it's used up
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum KeyRule {
+    Consumed,
+    Reusable,
+    Uses(u32),
+}
+
+impl KeyRule {
+    // door openings a single key is good for, None if it never wears out
+    pub fn uses_per_key(&self) -> Option<u32> {
+        match self {
+            KeyRule::Consumed => Some(1),
+            KeyRule::Reusable => None,
+            KeyRule::Uses(uses) => Some(*uses),
+        }
+    }
+}
+
+impl FromStr for KeyRule {
+    type Err = ();
+
+    // "consumed", "reusable" or a positive number of uses
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "consumed" => Ok(KeyRule::Consumed),
+            "reusable" => Ok(KeyRule::Reusable),
+            _ => match value.parse::<u32>() {
+                Ok(uses) if uses > 0 => Ok(KeyRule::Uses(uses)),
+                _ => Err(()),
+            },
+        }
+    }
+}
+
+// whether stepping on a teleporter pad moves the player to its partner, or teleporting is a separate move
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TeleportRule {
+    Forced,
+    Optional,
+}
+
+impl FromStr for TeleportRule {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "forced" => Ok(TeleportRule::Forced),
+            "optional" => Ok(TeleportRule::Optional),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct MazeState {
+    pub current_position: (usize, usize),
+    // starts of the agents after the first in a maze with several agents, the first one starts at current_position
+    pub other_agents: Vec<(usize, usize)>,
+    pub cells_with_locked_doors: Vec<(usize, usize, Direction)>,
+    // door sides the door can't be unlocked from, it has to be opened from the other side
+    pub sealed_doors: Vec<(usize, usize, Direction)>,
+    pub keys_left: Vec<(usize, usize)>,
+    // keys held per color, indexed like color_names
+    pub keys_held: Vec<u32>,
+    pub key_rule: KeyRule,
+    // most keys the player can carry at once, None if there's no limit
+    pub key_capacity: Option<u32>,
+    pub teleport_rule: TeleportRule,
+    pub action_costs: ActionCosts,
+    // keys and doors without an entry here have the default color 0
+    pub color_names: Vec<String>,
+    pub key_colors: HashMap<(usize, usize), usize>,
+    pub door_colors: HashMap<(usize, usize, Direction), usize>,
+    // gate sides with their group, gates are closed at the start unless listed in open_gates
+    pub gates: HashMap<(usize, usize, Direction), usize>,
+    pub open_gates: Vec<(usize, usize, Direction)>,
+    pub gate_names: Vec<String>,
+}
+
+impl MazeState {
+    pub fn new() -> Self {
+        Self {
+            current_position: (0, 0),
+            other_agents: Vec::new(),
+            cells_with_locked_doors: Vec::new(),
+            sealed_doors: Vec::new(),
+            keys_left: Vec::new(),
+            keys_held: vec![0],
+            key_rule: KeyRule::Consumed,
+            key_capacity: None,
+            teleport_rule: TeleportRule::Forced,
+            action_costs: ActionCosts::default(),
+            color_names: vec![String::from(DEFAULT_COLOR)],
+            key_colors: HashMap::new(),
+            door_colors: HashMap::new(),
+            gates: HashMap::new(),
+            open_gates: Vec::new(),
+            gate_names: Vec::new()
+        }
+    }
+
+    // start of every agent, in the order they are defined
+    pub fn agent_positions(&self) -> Vec<(usize, usize)> {
+        let mut agent_positions = vec![self.current_position];
+        agent_positions.extend(&self.other_agents);
+        return agent_positions;
+    }
+
+    // index of the color with the given name, added if it's new
+    pub fn color_index(&mut self, color_name: &str) -> usize {
+        if let Some(color) = self.color_names.iter().position(|name| name == color_name) {
+            return color;
+        }
+        self.color_names.push(String::from(color_name));
+        self.keys_held.push(0);
+        return self.color_names.len() - 1;
+    }
+
+    // index of the gate group with the given name, added if it's new
+    pub fn gate_group(&mut self, group_name: &str) -> usize {
+        if let Some(group) = self.gate_names.iter().position(|name| name == group_name) {
+            return group;
+        }
+        self.gate_names.push(String::from(group_name));
+        return self.gate_names.len() - 1;
+    }
+
+    pub fn key_color(&self, position: (usize, usize)) -> usize {
+        return *self.key_colors.get(&position).unwrap_or(&0);
+    }
+
+    pub fn door_color(&self, position: (usize, usize), direction: &Direction) -> usize {
+        return *self.door_colors.get(&(position.0, position.1, direction.clone())).unwrap_or(&0);
+    }
+}
+
+impl Default for MazeState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// position of the cell in the given direction, or None if it would be outside of the maze or on another floor
+pub fn neighbour_in_direction(maze_table: &Array2D<MazeCell>, position: (usize, usize), direction: &Direction) -> Option<(usize, usize)> {
+    let (row_index, col_index) = position;
+    let neighbour_position = match direction {
+        Direction::WEST if col_index > 0 => (row_index, col_index - 1),
+        Direction::EAST if col_index + 1 < maze_table.num_columns() => (row_index, col_index + 1),
+        Direction::NORTH if row_index > 0 => (row_index - 1, col_index),
+        Direction::SOUTH if row_index + 1 < maze_table.num_rows() => (row_index + 1, col_index),
+        _ => return None,
+    };
+    if maze_table[neighbour_position].level != maze_table[position].level {
+        return None;
+    }
+    return Some(neighbour_position);
+}
+
+// number of ticks after which every hazard repeats, the least common multiple of their periods (saturating)
+pub fn hazard_cycle(maze_table: &Array2D<MazeCell>) -> u64 {
+    let mut cycle: u64 = 1;
+    for maze_cell in maze_table.elements_row_major_iter() {
+        if let Some(hazard) = &maze_cell.hazard {
+            let (mut a, mut b) = (cycle, hazard.period as u64);
+            while b != 0 {
+                (a, b) = (b, a % b);
+            }
+            cycle = (cycle / a).saturating_mul(hazard.period as u64);
+        }
+    }
+    return cycle;
+}
+
+pub fn num_levels(maze_table: &Array2D<MazeCell>) -> usize {
+    return maze_table[(maze_table.num_rows() - 1, 0)].level + 1;
+}
+
+// position of a maze table position on its own floor
+pub fn floor_position(maze_table: &Array2D<MazeCell>, position: (usize, usize)) -> Position {
+    let floor_rows = maze_table.num_rows() / num_levels(maze_table);
+    return Position { level: position.0 / floor_rows, row: position.0 % floor_rows, col: position.1 };
+}
+
+// maze table position of a cell on the given floor
+pub fn table_position(maze_table: &Array2D<MazeCell>, position: Position) -> (usize, usize) {
+    let floor_rows = maze_table.num_rows() / num_levels(maze_table);
+    return (position.level * floor_rows + position.row, position.col);
+}
+
+// "(row, col)", or "(level, row, col)" in a maze with several floors
+pub fn format_position(maze_table: &Array2D<MazeCell>, position: (usize, usize)) -> String {
+    if num_levels(maze_table) == 1 {
+        return format!("({}, {})", position.0, position.1);
+    }
+    let Position { level, row, col } = floor_position(maze_table, position);
+    return format!("({}, {}, {})", level, row, col);
+}
+
+// position of the same cell one floor up or down, or None if there is no such floor
+pub fn cell_on_next_floor(maze_table: &Array2D<MazeCell>, position: (usize, usize), going_up: bool) -> Option<(usize, usize)> {
+    let floor_rows = maze_table.num_rows() / num_levels(maze_table);
+    let row_index = if going_up { position.0 + floor_rows } else { position.0.checked_sub(floor_rows)? };
+    if row_index >= maze_table.num_rows() {
+        return None;
+    }
+    return Some((row_index, position.1));
+}
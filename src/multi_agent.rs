@@ -0,0 +1,240 @@
+use array2d::Array2D;
+use std::collections::{ HashMap, VecDeque };
+use std::str::FromStr;
+use std::time::{ Duration, Instant };
+
+use crate::maze::{ MazeCell, MazeState, Move };
+use crate::solve::{ KeyAction, SearchProblem, SearchState, Unsolvable };
+
+// how the agents take turns
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Schedule {
+    // every agent moves or waits in every step, which minimizes the makespan
+    Joint,
+    // a single agent moves in every step, or nobody while a hazard passes, which minimizes the total number of moves
+    // as turns where nobody moves are free
+    TurnBased,
+}
+
+impl Schedule {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Schedule::Joint => "joint",
+            Schedule::TurnBased => "turns",
+        }
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "joint" => Ok(Schedule::Joint),
+            "turns" => Ok(Schedule::TurnBased),
+            _ => Err(()),
+        }
+    }
+}
+
+// where an agent is after a step of the solution, and what it did to get there
+#[derive(Clone, Debug)]
+pub struct TrackStep {
+    pub position: (usize, usize),
+    // false if the agent waited during the step
+    pub moved: bool,
+    pub picked_up_key: bool,
+    pub dropped_key: bool,
+    pub opened_door: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct MultiAgentSolution {
+    pub schedule: Schedule,
+    // every agent's track, starting at its start, with one entry per step
+    pub tracks: Vec<Vec<TrackStep>>,
+    pub states_expanded: usize,
+    pub elapsed: Duration,
+}
+
+impl MultiAgentSolution {
+    pub fn makespan(&self) -> usize {
+        self.tracks[0].len() - 1
+    }
+
+    pub fn total_moves(&self) -> usize {
+        self.tracks.iter().map(|track| track.iter().filter(|track_step| track_step.moved).count()).sum()
+    }
+}
+
+// positions of all agents with the key pool, doors and gates they share
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct JointState {
+    positions: Vec<(usize, usize)>,
+    // always at the first agent's position, so equal joint states hash the same
+    shared: SearchState,
+}
+
+// joint states are kept in an arena and point to the state they were reached from
+struct JointNode {
+    state: JointState,
+    parent: Option<usize>,
+    // part of every agent in the step that led here
+    agent_steps: Vec<TrackStep>,
+}
+
+fn waiting_step(position: (usize, usize)) -> TrackStep {
+    TrackStep { position, moved: false, picked_up_key: false, dropped_key: false, opened_door: false }
+}
+
+// every way a single agent can move from the joint state, the others wait
+fn agent_successors(problem: &SearchProblem, state: &JointState, agent: usize) -> Vec<(JointState, Vec<TrackStep>)> {
+    let agent_state = state.shared.at(state.positions[agent]);
+    let mut successors = Vec::new();
+    for next_move in problem.moves(&agent_state) {
+        // waiting is up to the schedule, as every agent waits whenever it doesn't move
+        if next_move == Move::Wait {
+            continue;
+        }
+        let Some(search_node) = problem.successor(0, &agent_state, &next_move) else { continue };
+        let mut positions = state.positions.clone();
+        positions[agent] = search_node.state.position();
+        let mut agent_steps: Vec<TrackStep> = state.positions.iter().map(|position| waiting_step(*position)).collect();
+        agent_steps[agent] = TrackStep {
+            position: positions[agent],
+            moved: true,
+            picked_up_key: matches!(search_node.key_action, Some(KeyAction::PickUp(_))),
+            dropped_key: matches!(search_node.key_action, Some(KeyAction::Drop(_))),
+            opened_door: search_node.opened_door.is_some(),
+        };
+        let shared = search_node.state.at(positions[0]);
+        successors.push((JointState { positions, shared }, agent_steps));
+    }
+    return successors;
+}
+
+// agents that stand still during a step are hit by hazards as well
+fn is_safe(problem: &SearchProblem, state: &JointState) -> bool {
+    state.positions.iter().all(|position| !problem.maze_table()[*position].is_blocked(state.shared.tick()))
+}
+
+// every combination of moves and waits, the agents act one after another within the step so they never
+// pick up the same key or spend the same key twice, but the whole step only takes a single tick
+fn joint_successors(problem: &SearchProblem, state: &JointState) -> Vec<(JointState, Vec<TrackStep>)> {
+    let tick = state.shared.tick();
+    let mut partial_steps: Vec<(JointState, Vec<TrackStep>)> = vec![(state.clone(), Vec::new())];
+    for agent in 0..state.positions.len() {
+        let mut next_partial_steps = Vec::new();
+        for (partial_state, agent_steps) in partial_steps {
+            let partial_state = JointState { shared: partial_state.shared.with_tick(tick), positions: partial_state.positions };
+            // waiting comes first, so agents that are done wait for the others instead of walking around
+            let mut waited_steps = agent_steps.clone();
+            waited_steps.push(waiting_step(partial_state.positions[agent]));
+            next_partial_steps.push((partial_state.clone(), waited_steps));
+            for (moved_state, moved_steps) in agent_successors(problem, &partial_state, agent) {
+                let mut agent_steps = agent_steps.clone();
+                agent_steps.push(moved_steps[agent].clone());
+                next_partial_steps.push((moved_state, agent_steps));
+            }
+        }
+        partial_steps = next_partial_steps;
+    }
+
+    // the step where everybody waits lets a hazard pass, without hazards it leads back to the same state
+    let next_tick = problem.next_tick(tick);
+    return partial_steps.into_iter()
+        .filter(|(_, agent_steps)| next_tick != tick || agent_steps.iter().any(|track_step| track_step.moved))
+        .map(|(joint_state, agent_steps)| (JointState { shared: joint_state.shared.with_tick(next_tick), positions: joint_state.positions }, agent_steps))
+        .filter(|(joint_state, _)| is_safe(problem, joint_state))
+        .collect();
+}
+
+fn build_solution(joint_nodes: &[JointNode], end_node_index: usize, schedule: Schedule, states_expanded: usize, elapsed: Duration) -> MultiAgentSolution {
+    let num_agents = joint_nodes[0].state.positions.len();
+    let mut tracks: Vec<Vec<TrackStep>> = vec![Vec::new(); num_agents];
+    let mut node_index = Some(end_node_index);
+    while let Some(index) = node_index {
+        for (agent, track_step) in joint_nodes[index].agent_steps.iter().enumerate() {
+            tracks[agent].push(track_step.clone());
+        }
+        node_index = joint_nodes[index].parent;
+    }
+    for track in tracks.iter_mut() {
+        track.reverse();
+    }
+
+    return MultiAgentSolution { schedule, tracks, states_expanded, elapsed };
+}
+
+// 0-1 BFS over the joint states until every agent stands on an exit, where every step counts once in the
+// joint schedule and only steps with a move count in the turn-based one
+pub fn solve_maze_multi_agent(maze_table: &Array2D<MazeCell>, initial_maze_state: &MazeState, schedule: Schedule) -> Result<MultiAgentSolution, Unsolvable> {
+    let now = Instant::now();
+    let mut states_expanded = 0;
+
+    let problem = SearchProblem::new(maze_table, initial_maze_state);
+    let positions = initial_maze_state.agent_positions();
+    let initial_state = JointState { shared: problem.initial_node().state.at(positions[0]), positions: positions.clone() };
+    let is_goal = |state: &JointState| state.positions.iter().all(|position| maze_table[*position].end_of_maze);
+
+    // lowest number of counted steps found so far for every joint state
+    let mut best_steps: HashMap<JointState, usize> = HashMap::new();
+    best_steps.insert(initial_state.clone(), 0);
+
+    let mut joint_nodes: Vec<JointNode> = vec![JointNode {
+        state: initial_state,
+        parent: None,
+        agent_steps: positions.iter().map(|position| waiting_step(*position)).collect(),
+    }];
+    let mut steps: Vec<usize> = vec![0];
+    let mut bfs_queue: VecDeque<usize> = VecDeque::new();
+    bfs_queue.push_back(0);
+
+    while let Some(node_index) = bfs_queue.pop_front() {
+        // a node with fewer counted steps was reached for this state after this one was queued
+        if best_steps[&joint_nodes[node_index].state] < steps[node_index] {
+            continue;
+        }
+
+        // found a step where every agent is on an exit
+        if is_goal(&joint_nodes[node_index].state) {
+            return Ok(build_solution(&joint_nodes, node_index, schedule, states_expanded, now.elapsed()));
+        }
+        states_expanded += 1;
+
+        let state = &joint_nodes[node_index].state;
+        let successors = match schedule {
+            Schedule::Joint => joint_successors(&problem, state),
+            Schedule::TurnBased => {
+                // a turn where nobody moves lets a hazard pass, without hazards it leads back to the same state
+                let waited_state = JointState { shared: state.shared.with_tick(problem.next_tick(state.shared.tick())), positions: state.positions.clone() };
+                let waited_steps = state.positions.iter().map(|position| waiting_step(*position)).collect();
+                (0..state.positions.len())
+                    .flat_map(|agent| agent_successors(&problem, state, agent))
+                    .chain([(waited_state, waited_steps)])
+                    .filter(|(joint_state, _)| is_safe(&problem, joint_state))
+                    .collect()
+            },
+        };
+        for (neighbour_state, agent_steps) in successors {
+            // waiting out a hazard is free in the turn-based schedule, so it goes to the front of the queue
+            let free_step = schedule == Schedule::TurnBased && agent_steps.iter().all(|track_step| !track_step.moved);
+            let neighbour_steps = if free_step { steps[node_index] } else { steps[node_index] + 1 };
+            if best_steps.get(&neighbour_state).is_some_and(|best| *best <= neighbour_steps) {
+                continue;
+            }
+
+            best_steps.insert(neighbour_state.clone(), neighbour_steps);
+            if free_step {
+                bfs_queue.push_front(joint_nodes.len());
+            }
+            else {
+                bfs_queue.push_back(joint_nodes.len());
+            }
+            joint_nodes.push(JointNode { state: neighbour_state, parent: Some(node_index), agent_steps });
+            steps.push(neighbour_steps);
+        }
+    }
+
+    return Err(Unsolvable { states_expanded, elapsed: now.elapsed() });
+}
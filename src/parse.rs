@@ -0,0 +1,497 @@
+use array2d::Array2D;
+use std::fmt;
+use std::fs::File;
+use std::io::{ self, BufRead };
+
+use crate::maze::{ Direction, MazeCell, MazeState, Switch, ALL_DIRECTIONS };
+
+const CELL_LINE_LENGTH: usize = 14;
+const SEPARATOR_COLUMNS: [usize; 2] = [4, 9];
+
+// largest number of cells over all floors, the maze table is allocated from the header before any cell line is read
+pub const MAX_CELLS: usize = 1_000_000;
+
+#[derive(Debug)]
+pub enum MazeParseError {
+    Io(io::Error),
+    MissingHeader,
+    InvalidHeader { line: usize, found: String },
+    MazeTooLarge { line: usize, max_cells: usize, found: String },
+    WrongLineLength { line: usize, expected: usize, found: usize },
+    NonBinaryDigit { line: usize, column: usize, found: char },
+    MissingSeparator { line: usize, column: usize, found: char },
+    HalfSetField { line: usize, column: usize, field: &'static str, found: String },
+    TooManyLines { line: usize, expected: usize, found: usize },
+    TooFewLines { line: usize, expected: usize, found: usize },
+    MissingStart,
+    MultipleStarts { first_line: usize, line: usize },
+    WrongNumberOfStarts { expected: usize, found: usize },
+    MalformedAttribute { line: usize, found: String },
+    UnknownAttribute { line: usize, name: String },
+    InvalidAttributeValue { line: usize, name: String, expected: &'static str, found: String },
+    AttributeWithoutTarget { line: usize, name: String, target: &'static str },
+    UnpairedTeleporter { line: usize, id: String },
+    ExtraTeleporter { line: usize, id: String, first_lines: (usize, usize) },
+}
+
+impl fmt::Display for MazeParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MazeParseError::Io(error) => write!(f, "couldn't read maze definition: {}", error),
+            MazeParseError::MissingHeader => write!(f, "line 1: expected header \"<rows> <cols>\", found end of file"),
+            MazeParseError::InvalidHeader { line, found } =>
+                write!(f, "line {}: expected header \"<rows> <cols>\" with two positive numbers, found \"{}\"", line, found),
+            MazeParseError::MazeTooLarge { line, max_cells, found } =>
+                write!(f, "line {}: a maze can have at most {} cells over all floors, found header \"{}\"", line, max_cells, found),
+            MazeParseError::WrongLineLength { line, expected, found } =>
+                write!(f, "line {}: expected {} characters in the form \"WENS WENS KKEE\" optionally followed by attributes, found {}", line, expected, found),
+            MazeParseError::NonBinaryDigit { line, column, found } =>
+                write!(f, "line {}, column {}: expected '0' or '1', found '{}'", line, column, found),
+            MazeParseError::MissingSeparator { line, column, found } =>
+                write!(f, "line {}, column {}: expected separator ' ', found '{}'", line, column, found),
+            MazeParseError::HalfSetField { line, column, field, found } =>
+                write!(f, "line {}, column {}: {} field must be {}, found \"{}\"", line, column, field, allowed_field_values(field), found),
+            MazeParseError::TooManyLines { line, expected, found } =>
+                write!(f, "line {}: expected only {} cell lines after the header, found {}", line, expected, found),
+            MazeParseError::TooFewLines { line, expected, found } =>
+                write!(f, "line {}: expected {} cell lines after the header, found end of file after {}", line, expected, found),
+            MazeParseError::MissingStart => write!(f, "no start cell, mark exactly one cell with key field \"01\""),
+            MazeParseError::MultipleStarts { first_line, line } =>
+                write!(f, "line {}: second start cell, the start is already defined on line {}", line, first_line),
+            MazeParseError::WrongNumberOfStarts { expected, found } =>
+                write!(f, "expected {} start cells, one for every agent, found {}", expected, found),
+            MazeParseError::MalformedAttribute { line, found } =>
+                write!(f, "line {}: expected attribute in the form \"name=value\", found \"{}\"", line, found),
+            MazeParseError::UnknownAttribute { line, name } => write!(f, "line {}: unknown attribute \"{}\"", line, name),
+            MazeParseError::InvalidAttributeValue { line, name, expected, found } =>
+                write!(f, "line {}: attribute \"{}\" must be {}, found \"{}\"", line, name, expected, found),
+            MazeParseError::AttributeWithoutTarget { line, name, target } =>
+                write!(f, "line {}: attribute \"{}\" needs {}", line, name, target),
+            MazeParseError::UnpairedTeleporter { line, id } => write!(f, "line {}: teleporter \"{}\" has no partner pad", line, id),
+            MazeParseError::ExtraTeleporter { line, id, first_lines } =>
+                write!(f, "line {}: teleporter \"{}\" already links the pads on lines {} and {}", line, id, first_lines.0, first_lines.1),
+        }
+    }
+}
+
+impl std::error::Error for MazeParseError {}
+
+impl From<io::Error> for MazeParseError {
+    fn from(error: io::Error) -> Self {
+        MazeParseError::Io(error)
+    }
+}
+
+fn allowed_field_values(field: &str) -> &'static str {
+    match field {
+        "key" => "\"00\", \"11\" (key) or \"01\" (start)",
+        _ => "\"00\" or \"11\"",
+    }
+}
+
+fn read_maze_dimensions(header: &str) -> Option<(usize, usize)> {
+    // header line starts with "<rows> <cols>", maze rules may follow
+    let mut parts = header.split_whitespace();
+    let num_rows = parts.next()?.parse::<usize>().ok()?;
+    let num_cols = parts.next()?.parse::<usize>().ok()?;
+    if num_rows == 0 || num_cols == 0 {
+        return None;
+    }
+    return Some((num_rows, num_cols));
+}
+
+// splits "name=value" attribute tokens
+fn read_attributes<'a>(tokens: impl Iterator<Item = &'a str>, line_number: usize) -> Result<Vec<(&'a str, &'a str)>, MazeParseError> {
+    let mut attributes = Vec::new();
+    for token in tokens {
+        match token.split_once('=') {
+            Some((name, value)) if !name.is_empty() && !value.is_empty() => attributes.push((name, value)),
+            _ => return Err(MazeParseError::MalformedAttribute { line: line_number, found: String::from(token) }),
+        }
+    }
+    return Ok(attributes);
+}
+
+fn parse_attribute_value<T: std::str::FromStr>(name: &str, value: &str, expected: &'static str, line_number: usize) -> Result<T, MazeParseError> {
+    return value.parse::<T>().map_err(|_| MazeParseError::InvalidAttributeValue {
+        line: line_number,
+        name: String::from(name),
+        expected,
+        found: String::from(value)
+    });
+}
+
+fn apply_header_rule(maze_state: &mut MazeState, name: &str, value: &str) -> Result<(), MazeParseError> {
+    match name {
+        "unlock" => maze_state.action_costs.unlock = parse_attribute_value(name, value, "a non-negative number", 1)?,
+        "pickup" => maze_state.action_costs.pickup = parse_attribute_value(name, value, "a non-negative number", 1)?,
+        "keys" => maze_state.key_rule = parse_attribute_value(name, value, "\"consumed\", \"reusable\" or a positive number of uses", 1)?,
+        "teleport" => maze_state.teleport_rule = parse_attribute_value(name, value, "\"forced\" or \"optional\"", 1)?,
+        "capacity" => {
+            let capacity: u32 = parse_attribute_value(name, value, "a positive number", 1)?;
+            if capacity == 0 {
+                return Err(MazeParseError::InvalidAttributeValue {
+                    line: 1,
+                    name: String::from(name),
+                    expected: "a positive number",
+                    found: String::from(value)
+                });
+            }
+            maze_state.key_capacity = Some(capacity);
+        },
+        _ => return Err(MazeParseError::UnknownAttribute { line: 1, name: String::from(name) }),
+    }
+    return Ok(());
+}
+
+const TELEPORTER_TARGET: &str = "a cell without a key, start, exit or switch";
+
+// teleporter pad with its id and the line it's defined on, paired up once the whole maze is read
+struct TeleporterPad {
+    id: String,
+    position: (usize, usize),
+    line: usize,
+}
+
+fn apply_cell_attribute(maze_cell: &mut MazeCell, maze_state: &mut MazeState, teleporter_pads: &mut Vec<TeleporterPad>, name: &str, value: &str, line_number: usize) -> Result<(), MazeParseError> {
+    let position = (maze_cell.row_index, maze_cell.col_index);
+    match name {
+        "cost" => {
+            maze_cell.cost = parse_attribute_value(name, value, "a positive number", line_number)?;
+            if maze_cell.cost == 0 {
+                return Err(MazeParseError::InvalidAttributeValue {
+                    line: line_number,
+                    name: String::from(name),
+                    expected: "a positive number",
+                    found: String::from(value)
+                });
+            }
+        },
+        "key" => {
+            if !maze_state.keys_left.contains(&position) {
+                return Err(MazeParseError::AttributeWithoutTarget { line: line_number, name: String::from(name), target: "a key on the cell" });
+            }
+            let color = maze_state.color_index(value);
+            maze_state.key_colors.insert(position, color);
+        },
+        // "door" colors every door of the cell, "doorW" and the others a single side
+        "door" | "doorW" | "doorE" | "doorN" | "doorS" => {
+            let doors: Vec<(usize, usize, Direction)> = maze_state.cells_with_locked_doors.iter()
+                .filter(|(row_index, col_index, direction)| {
+                    (*row_index, *col_index) == position && (name == "door" || &name[4..] == direction_letter(direction))
+                })
+                .cloned()
+                .collect();
+            if doors.is_empty() {
+                let target = if name == "door" { "a door on the cell" } else { "a door on that side of the cell" };
+                return Err(MazeParseError::AttributeWithoutTarget { line: line_number, name: String::from(name), target });
+            }
+            let color = maze_state.color_index(value);
+            for door in doors {
+                maze_state.door_colors.insert(door, color);
+            }
+        },
+        "tp" => {
+            // the player doesn't stay on a forced pad, so nothing may be waiting there
+            if maze_cell.end_of_maze || maze_state.keys_left.contains(&position) {
+                return Err(MazeParseError::AttributeWithoutTarget { line: line_number, name: String::from(name), target: TELEPORTER_TARGET });
+            }
+            teleporter_pads.push(TeleporterPad { id: String::from(value), position, line: line_number });
+        },
+        "hazard" => {
+            maze_cell.hazard = Some(parse_attribute_value(name, value, "\"<period>:<ticks>\" such as \"4:0,1\", with every tick below the period", line_number)?);
+        },
+        "stairs" => {
+            match value {
+                "U" => maze_cell.stairs_up = true,
+                "D" => maze_cell.stairs_down = true,
+                "UD" | "DU" => {
+                    maze_cell.stairs_up = true;
+                    maze_cell.stairs_down = true;
+                },
+                _ => return Err(MazeParseError::InvalidAttributeValue {
+                    line: line_number,
+                    name: String::from(name),
+                    expected: "\"U\", \"D\" or \"UD\"",
+                    found: String::from(value)
+                }),
+            }
+        },
+        "gateW" | "gateE" | "gateN" | "gateS" => {
+            let direction = ALL_DIRECTIONS.iter().find(|direction| &name[4..] == direction_letter(direction)).unwrap().clone();
+            if !maze_cell.available_directions.contains(&direction) {
+                return Err(MazeParseError::AttributeWithoutTarget { line: line_number, name: String::from(name), target: "a passage on that side of the cell" });
+            }
+            let group = maze_state.gate_group(value);
+            maze_state.gates.insert((position.0, position.1, direction), group);
+        },
+        "open" => {
+            for direction in parse_directions(name, value, line_number)? {
+                let gate = (position.0, position.1, direction);
+                if !maze_state.gates.contains_key(&gate) {
+                    return Err(MazeParseError::AttributeWithoutTarget { line: line_number, name: String::from(name), target: "a gate on every listed side" });
+                }
+                maze_state.open_gates.push(gate);
+            }
+        },
+        "plate" | "lever" => {
+            if maze_cell.switch.is_some() {
+                return Err(MazeParseError::AttributeWithoutTarget { line: line_number, name: String::from(name), target: "a cell without another switch" });
+            }
+            let group = maze_state.gate_group(value);
+            maze_cell.switch = Some(if name == "plate" { Switch::Plate(group) } else { Switch::Lever(group) });
+        },
+        "oneway" => {
+            for direction in parse_directions(name, value, line_number)? {
+                if !maze_cell.available_directions.contains(&direction) {
+                    return Err(MazeParseError::AttributeWithoutTarget { line: line_number, name: String::from(name), target: "a passage on every listed side" });
+                }
+                maze_cell.one_way.push(direction);
+            }
+        },
+        "sealed" => {
+            for direction in parse_directions(name, value, line_number)? {
+                let door = (position.0, position.1, direction);
+                if !maze_state.cells_with_locked_doors.contains(&door) {
+                    return Err(MazeParseError::AttributeWithoutTarget { line: line_number, name: String::from(name), target: "a door on every listed side" });
+                }
+                maze_state.sealed_doors.push(door);
+            }
+        },
+        _ => return Err(MazeParseError::UnknownAttribute { line: line_number, name: String::from(name) }),
+    }
+    return Ok(());
+}
+
+// every teleporter id has to be used by exactly two pads, and the start and switches can only be checked once the whole maze is read
+fn link_teleporter_pads(maze_table: &mut Array2D<MazeCell>, maze_state: &MazeState, teleporter_pads: &[TeleporterPad]) -> Result<(), MazeParseError> {
+    for (index, pad) in teleporter_pads.iter().enumerate() {
+        if maze_state.agent_positions().contains(&pad.position) || maze_table[pad.position].switch.is_some() {
+            return Err(MazeParseError::AttributeWithoutTarget { line: pad.line, name: String::from("tp"), target: TELEPORTER_TARGET });
+        }
+        let same_id: Vec<&TeleporterPad> = teleporter_pads.iter().filter(|other_pad| other_pad.id == pad.id).collect();
+        if same_id.len() == 1 {
+            return Err(MazeParseError::UnpairedTeleporter { line: pad.line, id: pad.id.clone() });
+        }
+        if same_id.len() > 2 {
+            return Err(MazeParseError::ExtraTeleporter { line: same_id[2].line, id: pad.id.clone(), first_lines: (same_id[0].line, same_id[1].line) });
+        }
+        let partner = teleporter_pads.iter().enumerate().find(|(other_index, other_pad)| *other_index != index && other_pad.id == pad.id).unwrap().1;
+        maze_table[pad.position].teleport_to = Some(partner.position);
+    }
+    return Ok(());
+}
+
+// direction letters such as "S" or "WE"
+fn parse_directions(name: &str, value: &str, line_number: usize) -> Result<Vec<Direction>, MazeParseError> {
+    let mut directions = Vec::new();
+    for letter in value.chars() {
+        match ALL_DIRECTIONS.iter().find(|direction| direction_letter(direction).starts_with(letter)) {
+            Some(direction) if !directions.contains(direction) => directions.push(direction.clone()),
+            _ => return Err(MazeParseError::InvalidAttributeValue {
+                line: line_number,
+                name: String::from(name),
+                expected: "one or more of the direction letters W, E, N and S",
+                found: String::from(value)
+            }),
+        }
+    }
+    return Ok(directions);
+}
+
+fn direction_letter(direction: &Direction) -> &'static str {
+    match direction {
+        Direction::WEST => "W",
+        Direction::EAST => "E",
+        Direction::NORTH => "N",
+        Direction::SOUTH => "S",
+    }
+}
+
+fn check_cell_line(line_vec: &[char], line_number: usize) -> Result<(), MazeParseError> {
+    // attributes after the cell fields must be separated by whitespace
+    if line_vec.len() < CELL_LINE_LENGTH || (line_vec.len() > CELL_LINE_LENGTH && !line_vec[CELL_LINE_LENGTH].is_whitespace()) {
+        return Err(MazeParseError::WrongLineLength { line: line_number, expected: CELL_LINE_LENGTH, found: line_vec.len() });
+    }
+
+    for (index, character) in line_vec[..CELL_LINE_LENGTH].iter().enumerate() {
+        if SEPARATOR_COLUMNS.contains(&index) {
+            if *character != ' ' {
+                return Err(MazeParseError::MissingSeparator { line: line_number, column: index + 1, found: *character });
+            }
+        }
+        else if *character != '0' && *character != '1' {
+            return Err(MazeParseError::NonBinaryDigit { line: line_number, column: index + 1, found: *character });
+        }
+    }
+
+    // key and exit flags are two-bit fields that are either fully set or fully unset,
+    // except for the spare key field pattern "01" which marks the start cell
+    for (index, field) in [(10, "key"), (12, "exit")] {
+        let is_start_marker = field == "key" && line_vec[index] == '0' && line_vec[index + 1] == '1';
+        if line_vec[index] != line_vec[index + 1] && !is_start_marker {
+            let found = line_vec[index..index + 2].iter().collect();
+            return Err(MazeParseError::HalfSetField { line: line_number, column: index + 1, field, found });
+        }
+    }
+
+    return Ok(());
+}
+
+pub fn read_maze_from_file(filename: String) -> Result<(Array2D<MazeCell>, MazeState), MazeParseError> {
+    let file = File::open(filename)?;
+    return read_maze(io::BufReader::new(file));
+}
+
+pub fn read_maze<R: BufRead>(reader: R) -> Result<(Array2D<MazeCell>, MazeState), MazeParseError> {
+    let mut lines = reader.lines();
+
+    let header = match lines.next() {
+        Some(header) => header?,
+        None => return Err(MazeParseError::MissingHeader),
+    };
+    let (num_rows, num_cols) = match read_maze_dimensions(&header) {
+        Some(dimensions) => dimensions,
+        None => return Err(MazeParseError::InvalidHeader { line: 1, found: header }),
+    };
+
+    // the number of floors decides the size of the maze table and the number of agents how many starts are expected,
+    // every other rule goes into the maze state
+    let mut maze_state = MazeState::new();
+    let mut num_levels = 1;
+    let mut num_agents = 1;
+    for (name, value) in read_attributes(header.split_whitespace().skip(2), 1)? {
+        if name == "levels" || name == "agents" {
+            let count = parse_attribute_value(name, value, "a positive number", 1)?;
+            if count == 0 {
+                return Err(MazeParseError::InvalidAttributeValue { line: 1, name: String::from(name), expected: "a positive number", found: String::from(value) });
+            }
+            if name == "levels" {
+                num_levels = count;
+            }
+            else {
+                num_agents = count;
+            }
+        }
+        else {
+            apply_header_rule(&mut maze_state, name, value)?;
+        }
+    }
+    let num_cells = match num_rows.checked_mul(num_cols).and_then(|floor_cells| floor_cells.checked_mul(num_levels)) {
+        Some(num_cells) if num_cells <= MAX_CELLS => num_cells,
+        _ => return Err(MazeParseError::MazeTooLarge { line: 1, max_cells: MAX_CELLS, found: header }),
+    };
+
+    // floors follow each other in the file and are stacked in the table
+    let mut maze_table = Array2D::<MazeCell>::filled_with(MazeCell::new(), num_levels * num_rows, num_cols);
+    let mut row_iter = 0;
+    let mut col_iter = 0;
+    let mut num_cell_lines = 0;
+    let mut start_line: Option<usize> = None;
+    let mut num_starts = 0;
+    let mut teleporter_pads: Vec<TeleporterPad> = Vec::new();
+    for line in lines {
+        let line = line?;
+        num_cell_lines += 1;
+        if num_cell_lines > num_cells {
+            continue;
+        }
+
+        // header is line 1, so cell lines start at line 2
+        let line_number = num_cell_lines + 1;
+        let line = line.trim_end_matches('\r');
+        let line_vec: Vec<char> = line.chars().collect();
+        check_cell_line(&line_vec, line_number)?;
+
+        let maze_cell = maze_table.get_mut(row_iter, col_iter).unwrap();
+        maze_cell.row_index = row_iter;
+        maze_cell.col_index = col_iter;
+        maze_cell.level = row_iter / num_rows;
+
+        if line_vec[0] == '1' {
+            let direction = Direction::WEST;
+            maze_cell.available_directions.push(direction);
+        }
+        if line_vec[1] == '1' {
+            let direction = Direction::EAST;
+            maze_cell.available_directions.push(direction);
+        }
+        if line_vec[2] == '1' {
+            let direction = Direction::NORTH;
+            maze_cell.available_directions.push(direction);
+        }
+        if line_vec[3] == '1' {
+            let direction = Direction::SOUTH;
+            maze_cell.available_directions.push(direction);
+        }
+
+        if line_vec[5] == '1' {
+            let direction = Direction::WEST;
+            maze_state.cells_with_locked_doors.push((row_iter, col_iter, direction));
+        }
+        if line_vec[6] == '1' {
+            let direction = Direction::EAST;
+            maze_state.cells_with_locked_doors.push((row_iter, col_iter, direction));
+        }
+        if line_vec[7] == '1' {
+            let direction = Direction::NORTH;
+            maze_state.cells_with_locked_doors.push((row_iter, col_iter, direction));
+        }
+        if line_vec[8] == '1' {
+            let direction = Direction::SOUTH;
+            maze_state.cells_with_locked_doors.push((row_iter, col_iter, direction));
+        }
+
+        if line_vec[10] == '1' && line_vec[11] == '1' {
+            maze_state.keys_left.push((row_iter, col_iter));
+        }
+
+        if line_vec[10] == '0' && line_vec[11] == '1' {
+            // every agent has a start cell, in the order of the agents
+            match start_line {
+                Some(first_line) if num_agents == 1 => return Err(MazeParseError::MultipleStarts { first_line, line: line_number }),
+                Some(_) => maze_state.other_agents.push((row_iter, col_iter)),
+                None => {
+                    start_line = Some(line_number);
+                    maze_state.current_position = (row_iter, col_iter);
+                },
+            }
+            num_starts += 1;
+        }
+
+        if line_vec[12] == '1' && line_vec[13] == '1' {
+            maze_cell.end_of_maze = true;
+        }
+
+        // the checked cell fields are ASCII, so the attributes start at the same byte offset,
+        // "open" goes last so it can refer to gates declared after it
+        let mut attributes = read_attributes(line[CELL_LINE_LENGTH..].split_whitespace(), line_number)?;
+        attributes.sort_by_key(|(name, _)| *name == "open");
+        for (name, value) in attributes {
+            apply_cell_attribute(maze_cell, &mut maze_state, &mut teleporter_pads, name, value, line_number)?;
+        }
+
+        col_iter += 1;
+        if col_iter >= num_cols {
+            row_iter += 1;
+            col_iter = 0;
+        }
+    }
+
+    // report the first line that is missing or surplus
+    if num_cell_lines > num_cells {
+        return Err(MazeParseError::TooManyLines { line: num_cells + 2, expected: num_cells, found: num_cell_lines });
+    }
+    if num_cell_lines < num_cells {
+        return Err(MazeParseError::TooFewLines { line: num_cell_lines + 2, expected: num_cells, found: num_cell_lines });
+    }
+    if start_line.is_none() {
+        return Err(MazeParseError::MissingStart);
+    }
+    if num_starts != num_agents {
+        return Err(MazeParseError::WrongNumberOfStarts { expected: num_agents, found: num_starts });
+    }
+    link_teleporter_pads(&mut maze_table, &maze_state, &teleporter_pads)?;
+
+    return Ok((maze_table, maze_state));
+}
@@ -0,0 +1,266 @@
+use array2d::Array2D;
+use std::collections::HashSet;
+
+use crate::maze::{ format_position, num_levels, Direction, MazeCell, MazeState, Move, Switch };
+use crate::explore::Exploration;
+use crate::multi_agent::MultiAgentSolution;
+use crate::solve::{ KeyAction, Solution };
+
+// every floor of a maze with several floors is drawn under its own heading
+fn print_floor_heading(maze_table: &Array2D<MazeCell>, row_index: usize) {
+    let levels = num_levels(maze_table);
+    let floor_rows = maze_table.num_rows() / levels;
+    if levels > 1 && row_index.is_multiple_of(floor_rows) {
+        println!("Floor {}:\n", row_index / floor_rows);
+    }
+}
+
+pub fn write_and_draw_solution(solution: &Solution, maze_table: &Array2D<MazeCell>, keyword: String) {
+    let indexes = if num_levels(maze_table) == 1 { "(row, col)" } else { "(level, row, col)" };
+    println!("Action log of {} solution with {} indexes, one move per step:\n", keyword, indexes);
+    // doors are listed in the order they were opened, and a door is opened the first time it's walked through
+    let mut doors_opened = solution.doors_opened.iter().peekable();
+    for (iter, position) in solution.path.iter().enumerate() {
+        if iter == 0 {
+            print!("{}. start at {}", iter + 1, format_position(maze_table, *position));
+        }
+        else {
+            let last_move = &solution.moves[iter - 1];
+            print!("{}. {} to {}", iter + 1, last_move.letter(), format_position(maze_table, *position));
+            match last_move {
+                Move::Pull => print!(", pulled the lever"),
+                Move::Wait => print!(", waited"),
+                _ => {},
+            }
+        }
+        if let Some((_, pad_position)) = solution.teleports.iter().find(|(path_index, _)| *path_index == iter) {
+            print!(", teleported from {}", format_position(maze_table, *pad_position));
+        }
+        if solution.switches_toggled.contains(&iter) && iter > 0 && solution.moves[iter - 1] != Move::Pull {
+            print!(", stepped on the plate");
+        }
+        match solution.key_actions.iter().find(|(path_index, _)| *path_index == iter) {
+            Some((_, KeyAction::PickUp(_))) => print!(", picked up key at {}", format_position(maze_table, *position)),
+            Some((_, KeyAction::Drop(_))) => print!(", dropped key at {}", format_position(maze_table, *position)),
+            None => {},
+        }
+        // a door onto a forced teleporter pad leads to the pad, not to the partner the move ended on
+        let door_position = solution.teleports.iter().find(|(path_index, _)| *path_index == iter).map_or(*position, |(_, pad_position)| *pad_position);
+        if iter > 0 && doors_opened.peek() == Some(&&(solution.path[iter - 1], door_position)) {
+            let (from_position, to_position) = doors_opened.next().unwrap();
+            print!(", unlocked door between {} and {}", format_position(maze_table, *from_position), format_position(maze_table, *to_position));
+        }
+        let keys_held = solution.keys_held[iter];
+        println!(", holding {} {}", keys_held, if keys_held == 1 { "key" } else { "keys" });
+    }
+    println!("\nMove string: {}", solution.move_string());
+
+    println!("\nKeys picked up:");
+    for position in &solution.keys_picked_up {
+        println!("{}", format_position(maze_table, *position));
+    }
+    let keys_dropped: Vec<(usize, usize)> = solution.key_actions.iter()
+        .filter(|(_, key_action)| matches!(key_action, KeyAction::Drop(_)))
+        .map(|(path_index, _)| solution.path[*path_index])
+        .collect();
+    if !keys_dropped.is_empty() {
+        println!("\nKeys dropped:");
+        for position in keys_dropped {
+            println!("{}", format_position(maze_table, position));
+        }
+    }
+    println!("\nDoors opened:");
+    for (from_position, to_position) in &solution.doors_opened {
+        println!("{} -> {}", format_position(maze_table, *from_position), format_position(maze_table, *to_position));
+    }
+
+    let teleport_legend = if solution.teleports.is_empty() { "" } else { "; T = teleporter pad used" };
+    let switch_legend = if solution.switches_toggled.is_empty() { "" } else { "; S = switch toggled" };
+    println!("\nEnd of {} solution ({} moves, cost {}, {} states expanded).\n\nTable representation of solution (0 = untraversed; 1 = traversed{}{}):\n",
+        keyword, solution.path.len() - 1, solution.cost, solution.states_expanded, teleport_legend, switch_legend);
+    for (row_index, iterator) in maze_table.rows_iter().enumerate() {
+        print_floor_heading(maze_table, row_index);
+        for maze_cell in iterator {
+            let position = (maze_cell.row_index, maze_cell.col_index);
+            if solution.teleports.iter().any(|(_, pad_position)| *pad_position == position) {
+                print!("T  ");
+            }
+            else if solution.switches_toggled.iter().any(|path_index| solution.path[*path_index] == position) {
+                print!("S  ");
+            }
+            else if solution.path.contains(&position) {
+                print!("1  ");
+            }
+            else {
+                print!("0  ");
+            }
+        }
+        println!("\n");
+    }
+}
+
+pub fn write_and_draw_multi_agent_solution(solution: &MultiAgentSolution, maze_table: &Array2D<MazeCell>) {
+    let keyword = solution.schedule.name();
+    let indexes = if num_levels(maze_table) == 1 { "(row, col)" } else { "(level, row, col)" };
+    println!("{} indexes of the {} solution in order, one track per agent:", indexes, keyword);
+    for (agent, track) in solution.tracks.iter().enumerate() {
+        println!("\nAgent {}:\n", agent + 1);
+        for (iter, track_step) in track.iter().enumerate() {
+            print!("{}. {}", iter + 1, format_position(maze_table, track_step.position));
+            if iter > 0 && !track_step.moved {
+                print!(" waited");
+            }
+            if track_step.opened_door {
+                print!(" opened a door");
+            }
+            if track_step.picked_up_key {
+                print!(" picked up a key");
+            }
+            if track_step.dropped_key {
+                print!(" dropped a key");
+            }
+            println!();
+        }
+    }
+
+    println!("\nEnd of {} solution (makespan {}, {} moves in total, {} states expanded).",
+        keyword, solution.makespan(), solution.total_moves(), solution.states_expanded);
+    for (agent, track) in solution.tracks.iter().enumerate() {
+        println!("\nTable representation of agent {}'s track (0 = untraversed; 1 = traversed):\n", agent + 1);
+        for (row_index, iterator) in maze_table.rows_iter().enumerate() {
+            print_floor_heading(maze_table, row_index);
+            for maze_cell in iterator {
+                let position = (maze_cell.row_index, maze_cell.col_index);
+                if track.iter().any(|track_step| track_step.position == position) {
+                    print!("1  ");
+                }
+                else {
+                    print!("0  ");
+                }
+            }
+            println!("\n");
+        }
+    }
+}
+
+pub fn write_and_draw_exploration(exploration: &Exploration, maze_table: &Array2D<MazeCell>, optimum: Option<usize>) {
+    let indexes = if num_levels(maze_table) == 1 { "(row, col)" } else { "(level, row, col)" };
+    println!("{} indexes of the explorer's walk in order:\n", indexes);
+    for (iter, position) in exploration.path.iter().enumerate() {
+        println!("{}. {}", iter + 1, format_position(maze_table, *position));
+    }
+
+    let outcome = if exploration.escaped { "escaped" } else { "found no way out" };
+    let optimum = match optimum {
+        Some(moves) => format!("{} moves", moves),
+        None => String::from("no solution"),
+    };
+    println!("\nExplorer {} after {} steps, replanning {} times (omniscient BFS optimum: {}).\n\nTable representation of the walk (0 = seen; 1 = walked; ? = never seen):\n",
+        outcome, exploration.steps(), exploration.replans, optimum);
+    // the walk can pass every cell many times, so it's looked up in a set
+    let walked: HashSet<&(usize, usize)> = exploration.path.iter().collect();
+    for (row_index, iterator) in maze_table.rows_iter().enumerate() {
+        print_floor_heading(maze_table, row_index);
+        for maze_cell in iterator {
+            let position = (maze_cell.row_index, maze_cell.col_index);
+            if walked.contains(&position) {
+                print!("1  ");
+            }
+            else if exploration.seen[position] {
+                print!("0  ");
+            }
+            else {
+                print!("?  ");
+            }
+        }
+        println!("\n");
+    }
+}
+
+// name of a non-default key or door color, e.g. "[red]"
+fn color_tag(initial_maze_state: &MazeState, color: usize) -> String {
+    if color == 0 {
+        return String::new();
+    }
+    return format!("[{}]", initial_maze_state.color_names[color]);
+}
+
+pub fn draw_initial_maze(initial_maze_state: &MazeState, maze_table: &Array2D<MazeCell>) {
+    let color_legend = if initial_maze_state.color_names.len() > 1 { "; [color] = key or door color" } else { "" };
+    let sealed_legend = if initial_maze_state.sealed_doors.is_empty() { "" } else { "; X = door that can't be unlocked from this side" };
+    let teleport_legend = if maze_table.elements_row_major_iter().any(|maze_cell| maze_cell.teleport_to.is_some()) { "; T(...) = teleporter pad to (...)" } else { "" };
+    let stairs_legend = if num_levels(maze_table) > 1 { "; ⇑ / ⇓ = stairs up / down" } else { "" };
+    let gate_legend = if initial_maze_state.gate_names.is_empty() { "" } else { "; G[group] / g[group] = closed / open gate; P[group] / L[group] = plate / lever toggling the group" };
+    let hazard_legend = if maze_table.elements_row_major_iter().any(|maze_cell| maze_cell.hazard.is_some()) { "; H<period>:<ticks> = blocked on these ticks of every period" } else { "" };
+    println!("\nTable representation of initial maze (0 = empty; 1 = key; 2 = exit; 3 = start{}{}{}{}{}{}):\n", color_legend, sealed_legend, teleport_legend, stairs_legend, gate_legend, hazard_legend);
+    for (row_index, iterator) in maze_table.rows_iter().enumerate() {
+        print_floor_heading(maze_table, row_index);
+        for maze_cell in iterator {
+            let position = (maze_cell.row_index, maze_cell.col_index);
+            // door and gate on one side of the cell, if any
+            let side_tag = |direction: Direction| {
+                let side = (position.0, position.1, direction.clone());
+                let mut tag = String::new();
+                if initial_maze_state.cells_with_locked_doors.contains(&side) {
+                    let door_letter = if initial_maze_state.sealed_doors.contains(&side) { "X" } else { "D" };
+                    tag += &format!("{}{}", door_letter, color_tag(initial_maze_state, initial_maze_state.door_color(position, &direction)));
+                }
+                if let Some(group) = initial_maze_state.gates.get(&side) {
+                    let gate_letter = if initial_maze_state.open_gates.contains(&side) { "g" } else { "G" };
+                    tag += &format!("{}[{}]", gate_letter, initial_maze_state.gate_names[*group]);
+                }
+                tag
+            };
+
+            let mut cell_num = 0;
+            let mut key_tag = String::new();
+            if initial_maze_state.keys_left.contains(&position) {
+                cell_num = 1;
+                key_tag = color_tag(initial_maze_state, initial_maze_state.key_color(position));
+            }
+            if initial_maze_state.agent_positions().contains(&position) {
+                cell_num = 3;
+            }
+            if maze_cell.end_of_maze {
+                cell_num = 2;
+            }
+
+            if maze_cell.available_directions.contains(&Direction::WEST) {
+                print!("{}<-", side_tag(Direction::WEST));
+            }
+            if maze_cell.available_directions.contains(&Direction::NORTH) {
+                print!("{}↑", side_tag(Direction::NORTH));
+            }
+
+            print!("{}{}", cell_num, key_tag);
+            if let Some(partner_position) = maze_cell.teleport_to {
+                print!("T{}", format_position(maze_table, partner_position));
+            }
+            if maze_cell.stairs_up {
+                print!("⇑");
+            }
+            if maze_cell.stairs_down {
+                print!("⇓");
+            }
+            match maze_cell.switch {
+                Some(Switch::Plate(group)) => print!("P[{}]", initial_maze_state.gate_names[group]),
+                Some(Switch::Lever(group)) => print!("L[{}]", initial_maze_state.gate_names[group]),
+                None => {},
+            }
+            if let Some(hazard) = &maze_cell.hazard {
+                let blocked_ticks: Vec<String> = hazard.blocked_ticks.iter().map(|tick| tick.to_string()).collect();
+                print!("H{}:{}", hazard.period, blocked_ticks.join(","));
+            }
+
+            if maze_cell.available_directions.contains(&Direction::SOUTH) {
+                print!("↓{}", side_tag(Direction::SOUTH));
+            }
+            if maze_cell.available_directions.contains(&Direction::EAST) {
+                print!("->{}", side_tag(Direction::EAST));
+            }
+
+            print!("  ");
+        }
+        println!("\n");
+    }
+}
@@ -0,0 +1,965 @@
+use array2d::Array2D;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{ HashMap, HashSet, VecDeque };
+use std::fmt;
+use std::hash::{ Hash, Hasher };
+use std::sync::{ Mutex, mpsc };
+use std::thread;
+use std::time::{ Duration, Instant };
+
+use crate::maze::{ cell_on_next_floor, floor_position, hazard_cycle, neighbour_in_direction, ActionCosts, Direction, KeyRule, MazeCell, MazeState, Move, Position, Switch, TeleportRule };
+
+// a key of the color taken or put down by the player
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum KeyAction {
+    PickUp(usize),
+    Drop(usize),
+}
+
+#[derive(Clone, Debug)]
+pub struct Solution {
+    // maze table positions, which count the rows of the floors below in a maze with several floors
+    pub path: Vec<(usize, usize)>,
+    // the same cells as the path, each with its floor
+    pub positions: Vec<Position>,
+    pub keys_picked_up: Vec<(usize, usize)>,
+    // index of every path position a key was picked up or dropped on, in order
+    pub key_actions: Vec<(usize, KeyAction)>,
+    pub doors_opened: Vec<((usize, usize), (usize, usize))>,
+    // index of the path position a teleporter was used to reach, with the pad it was used from
+    pub teleports: Vec<(usize, (usize, usize))>,
+    // index of every path position a switch was toggled on
+    pub switches_toggled: Vec<usize>,
+    // move that reached every path position after the start
+    pub moves: Vec<Move>,
+    // keys carried at every path position
+    pub keys_held: Vec<u32>,
+    pub cost: u64,
+    pub states_expanded: usize,
+    pub elapsed: Duration,
+}
+
+impl Solution {
+    // walks the parent indexes back from the end node, so the path is only built once
+    pub fn new(problem: &SearchProblem, search_nodes: &[SearchNode], end_node_index: usize, states_expanded: usize, elapsed: Duration) -> Self {
+        let mut path: Vec<(usize, usize)> = Vec::new();
+        let mut keys_picked_up: Vec<(usize, usize)> = Vec::new();
+        let mut doors_opened: Vec<((usize, usize), (usize, usize))> = Vec::new();
+        let mut teleport_pads: Vec<Option<(usize, usize)>> = Vec::new();
+        let mut toggled_switches: Vec<bool> = Vec::new();
+        let mut node_key_actions: Vec<Option<KeyAction>> = Vec::new();
+        let mut moves: Vec<Move> = Vec::new();
+        let mut keys_held: Vec<u32> = Vec::new();
+        let mut cost = 0;
+
+        let mut node_index = Some(end_node_index);
+        while let Some(index) = node_index {
+            let search_node = &search_nodes[index];
+            path.push(search_node.state.position);
+            cost += search_node.move_cost;
+            teleport_pads.push(search_node.teleported_from);
+            toggled_switches.push(search_node.toggled_switch);
+            node_key_actions.push(search_node.key_action);
+            moves.extend(search_node.last_move.clone());
+            keys_held.push(problem.keys_held(&search_node.state));
+            if let Some(KeyAction::PickUp(_)) = search_node.key_action {
+                keys_picked_up.push(search_node.state.position);
+            }
+            if let (Some(door_position), Some(parent_index)) = (search_node.opened_door, search_node.parent) {
+                doors_opened.push((search_nodes[parent_index].state.position, door_position));
+            }
+            node_index = search_node.parent;
+        }
+
+        path.reverse();
+        keys_picked_up.reverse();
+        doors_opened.reverse();
+        teleport_pads.reverse();
+        toggled_switches.reverse();
+        node_key_actions.reverse();
+        moves.reverse();
+        keys_held.reverse();
+        let teleports = teleport_pads.into_iter().enumerate()
+            .filter_map(|(path_index, pad_position)| pad_position.map(|pad_position| (path_index, pad_position)))
+            .collect();
+        let switches_toggled = toggled_switches.into_iter().enumerate()
+            .filter_map(|(path_index, toggled_switch)| toggled_switch.then_some(path_index))
+            .collect();
+        let key_actions = node_key_actions.into_iter().enumerate()
+            .filter_map(|(path_index, key_action)| key_action.map(|key_action| (path_index, key_action)))
+            .collect();
+        let positions = path.iter().map(|position| floor_position(problem.maze_table(), *position)).collect();
+        Self {
+            path,
+            positions,
+            keys_picked_up,
+            key_actions,
+            doors_opened,
+            teleports,
+            switches_toggled,
+            moves,
+            keys_held,
+            cost,
+            states_expanded,
+            elapsed
+        }
+    }
+
+    // one letter per move, e.g. "EESSWN"
+    pub fn move_string(&self) -> String {
+        self.moves.iter().map(Move::letter).collect()
+    }
+}
+
+// search statistics of a maze without a solution
+#[derive(Clone, Debug)]
+pub struct Unsolvable {
+    pub states_expanded: usize,
+    pub elapsed: Duration,
+}
+
+impl fmt::Display for Unsolvable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "no solution after expanding {} states", self.states_expanded)
+    }
+}
+
+impl std::error::Error for Unsolvable {}
+
+// full logical search state, with keys left on the board, locked doors and toggled gate groups as bitmasks indexed by key, door and group id
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct SearchState {
+    position: (usize, usize),
+    keys_left: Vec<u64>,
+    locked_doors: Vec<u64>,
+    // gate groups toggled an odd number of times, their gates are the other way round than at the start
+    toggled_gates: Vec<u64>,
+    // ticks since the start modulo the hazard cycle, always 0 without hazards
+    tick: u64,
+    // door openings left per key color, keys of one color are interchangeable so only the total matters
+    key_uses_left: Vec<u32>,
+    // keys carried as (color, door openings left), and keys put down as (position, color, door openings left),
+    // both in sorted order and only kept with a key capacity, as then it matters which key is dropped
+    keys_carried: Vec<(usize, u32)>,
+    dropped_keys: Vec<((usize, usize), usize, u32)>,
+}
+
+impl SearchState {
+    pub fn position(&self) -> (usize, usize) {
+        self.position
+    }
+
+    pub fn key_uses_left(&self) -> &[u32] {
+        &self.key_uses_left
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    // the same keys, doors and gates with the player somewhere else
+    pub fn at(&self, position: (usize, usize)) -> SearchState {
+        SearchState { position, ..self.clone() }
+    }
+
+    // the same state at another tick of the hazard cycle
+    pub(crate) fn with_tick(&self, tick: u64) -> SearchState {
+        SearchState { tick, ..self.clone() }
+    }
+}
+
+// search states are kept in an arena and point to the state they were reached from
+#[derive(Clone)]
+pub struct SearchNode {
+    pub state: SearchState,
+    pub parent: Option<usize>,
+    // move from the parent, None at the start
+    pub last_move: Option<Move>,
+    // key picked up or dropped by the move
+    pub key_action: Option<KeyAction>,
+    // cell on the far side of the door the move unlocked, which is the pad if the move teleported past it
+    pub opened_door: Option<(usize, usize)>,
+    // cost of the move from the parent, including unlocking and pickup
+    pub move_cost: u64,
+    // teleporter pad the move jumped from
+    pub teleported_from: Option<(usize, usize)>,
+    pub toggled_switch: bool,
+}
+
+fn set_bit(bitmask: &mut [u64], index: usize) {
+    bitmask[index / 64] |= 1 << (index % 64);
+}
+
+fn clear_bit(bitmask: &mut [u64], index: usize) {
+    bitmask[index / 64] &= !(1 << (index % 64));
+}
+
+fn toggle_bit(bitmask: &mut [u64], index: usize) {
+    bitmask[index / 64] ^= 1 << (index % 64);
+}
+
+fn is_bit_set(bitmask: &[u64], index: usize) -> bool {
+    bitmask[index / 64] & (1 << (index % 64)) != 0
+}
+
+// key and door ids of every cell with their colors, and the gates, fixed for the whole search
+struct MazeIndex {
+    key_ids: Array2D<Option<usize>>,
+    door_ids: Array2D<[Option<usize>; 4]>,
+    sealed_doors: Array2D<[bool; 4]>,
+    // gate group of every passage side, and whether the gate is open at the start
+    gates: Array2D<[Option<(usize, bool)>; 4]>,
+    num_gate_groups: usize,
+    hazard_cycle: u64,
+    key_colors: Vec<usize>,
+    door_colors: Vec<usize>,
+    num_keys: usize,
+    num_doors: usize,
+    key_rule: KeyRule,
+    key_capacity: Option<u32>,
+    teleport_rule: TeleportRule,
+    action_costs: ActionCosts,
+}
+
+impl MazeIndex {
+    fn new(maze_table: &Array2D<MazeCell>, initial_maze_state: &MazeState) -> Self {
+        let mut key_ids = Array2D::filled_with(None, maze_table.num_rows(), maze_table.num_columns());
+        let mut key_colors = Vec::new();
+        for (key_id, key_position) in initial_maze_state.keys_left.iter().enumerate() {
+            key_ids[*key_position] = Some(key_id);
+            key_colors.push(initial_maze_state.key_color(*key_position));
+        }
+
+        // both sides of a door are unlocked together, so they share an id
+        let mut door_ids = Array2D::filled_with([None; 4], maze_table.num_rows(), maze_table.num_columns());
+        let mut door_colors = Vec::new();
+        let mut num_doors = 0;
+        for (row_index, col_index, direction) in &initial_maze_state.cells_with_locked_doors {
+            if door_ids[(*row_index, *col_index)][direction.index()].is_some() {
+                continue;
+            }
+            door_ids[(*row_index, *col_index)][direction.index()] = Some(num_doors);
+            door_colors.push(initial_maze_state.door_color((*row_index, *col_index), direction));
+            if let Some(neighbour_position) = neighbour_in_direction(maze_table, (*row_index, *col_index), direction) {
+                door_ids[neighbour_position][direction.opposite().index()] = Some(num_doors);
+            }
+            num_doors += 1;
+        }
+
+        let mut sealed_doors = Array2D::filled_with([false; 4], maze_table.num_rows(), maze_table.num_columns());
+        for (row_index, col_index, direction) in &initial_maze_state.sealed_doors {
+            sealed_doors[(*row_index, *col_index)][direction.index()] = true;
+        }
+
+        let mut maze_index = Self {
+            key_ids,
+            door_ids,
+            sealed_doors,
+            gates: Array2D::filled_with([None; 4], maze_table.num_rows(), maze_table.num_columns()),
+            num_gate_groups: initial_maze_state.gate_names.len(),
+            hazard_cycle: hazard_cycle(maze_table),
+            key_colors,
+            door_colors,
+            num_keys: initial_maze_state.keys_left.len(),
+            num_doors,
+            key_rule: initial_maze_state.key_rule,
+            key_capacity: initial_maze_state.key_capacity,
+            teleport_rule: initial_maze_state.teleport_rule,
+            action_costs: initial_maze_state.action_costs,
+        };
+        for (gate, group) in &initial_maze_state.gates {
+            maze_index.add_gate(maze_table, gate, *group, initial_maze_state.open_gates.contains(gate));
+        }
+        return maze_index;
+    }
+
+    // a gate blocks its passage from both sides, even if it's only declared on one
+    fn add_gate(&mut self, maze_table: &Array2D<MazeCell>, gate: &(usize, usize, Direction), group: usize, open: bool) {
+        let (row_index, col_index, direction) = gate;
+        self.gates[(*row_index, *col_index)][direction.index()] = Some((group, open));
+        if let Some(neighbour_position) = neighbour_in_direction(maze_table, (*row_index, *col_index), direction) {
+            self.gates[neighbour_position][direction.opposite().index()].get_or_insert((group, open));
+        }
+    }
+
+    // door openings the given number of keys of one color are good for, reusable keys only count once
+    fn key_uses(&self, num_keys: u32) -> u32 {
+        match self.key_rule.uses_per_key() {
+            Some(uses) => num_keys.saturating_mul(uses),
+            None => num_keys.min(1),
+        }
+    }
+
+    // whether the player can take another key, always true without a capacity
+    fn has_room(&self, state: &SearchState) -> bool {
+        match self.key_capacity {
+            Some(capacity) => (state.keys_carried.len() as u32) < capacity,
+            None => true,
+        }
+    }
+
+    // whether the capacity can still stop the player from taking a key, which is false once every key
+    // still lying in the maze fits next to the ones carried, and dropping a key then only wastes a move
+    fn capacity_can_bind(&self, state: &SearchState) -> bool {
+        match self.key_capacity {
+            Some(capacity) => {
+                let keys_lying = state.keys_left.iter().map(|bits| bits.count_ones()).sum::<u32>() + state.dropped_keys.len() as u32;
+                state.keys_carried.len() as u32 + keys_lying > capacity
+            },
+            None => false,
+        }
+    }
+
+    // keys the player carries in the state, reusable keys of one color only count once without a capacity
+    fn keys_held(&self, state: &SearchState) -> u32 {
+        if self.key_capacity.is_some() {
+            return state.keys_carried.len() as u32;
+        }
+        match self.key_rule.uses_per_key() {
+            Some(uses) => state.key_uses_left.iter().map(|uses_left| uses_left.div_ceil(uses)).sum(),
+            None => state.key_uses_left.iter().sum(),
+        }
+    }
+
+    // adds a key of the color, good for the given door openings, to the keys the player carries
+    fn take_key(&self, state: &mut SearchState, color: usize, uses: u32) {
+        state.key_uses_left[color] = match self.key_rule {
+            KeyRule::Reusable => 1,
+            _ => state.key_uses_left[color].saturating_add(uses),
+        };
+        if self.key_capacity.is_some() {
+            let key = (color, uses);
+            let index = state.keys_carried.binary_search(&key).unwrap_or_else(|index| index);
+            state.keys_carried.insert(index, key);
+        }
+    }
+
+    fn initial_state(&self, initial_maze_state: &MazeState) -> SearchState {
+        let mut keys_left = vec![0; self.num_keys.div_ceil(64)];
+        for key_id in 0..self.num_keys {
+            set_bit(&mut keys_left, key_id);
+        }
+
+        let mut locked_doors = vec![0; self.num_doors.div_ceil(64)];
+        for door_id in 0..self.num_doors {
+            set_bit(&mut locked_doors, door_id);
+        }
+
+        // keys held at the start are fresh
+        let mut keys_carried = Vec::new();
+        if self.key_capacity.is_some() {
+            for (color, keys) in initial_maze_state.keys_held.iter().enumerate() {
+                keys_carried.extend((0..*keys).map(|_| (color, self.key_uses(1))));
+            }
+        }
+
+        SearchState {
+            position: initial_maze_state.current_position,
+            keys_left,
+            locked_doors,
+            toggled_gates: vec![0; self.num_gate_groups.div_ceil(64)],
+            tick: 0,
+            key_uses_left: initial_maze_state.keys_held.iter().map(|keys| self.key_uses(*keys)).collect(),
+            keys_carried,
+            dropped_keys: Vec::new(),
+        }
+    }
+}
+
+// every move takes a tick, and can't end on a cell a hazard blocks at the next tick
+fn finish_move(maze_table: &Array2D<MazeCell>, maze_index: &MazeIndex, mut search_node: SearchNode) -> Option<SearchNode> {
+    search_node.state.tick = (search_node.state.tick + 1) % maze_index.hazard_cycle;
+    if maze_table[search_node.state.position].is_blocked(search_node.state.tick) {
+        return None;
+    }
+    return Some(search_node);
+}
+
+fn get_successor(maze_table: &Array2D<MazeCell>, maze_index: &MazeIndex, parent_index: usize, state: &SearchState, next_move: &Move) -> Option<SearchNode> {
+    let (mut neighbour_position, direction) = match next_move {
+        Move::Step(direction) => (neighbour_in_direction(maze_table, state.position, direction)?, Some(direction)),
+        Move::Up | Move::Down => {
+            let maze_cell = &maze_table[state.position];
+            let going_up = *next_move == Move::Up;
+            if (going_up && !maze_cell.stairs_up) || (!going_up && !maze_cell.stairs_down) {
+                return None;
+            }
+            (cell_on_next_floor(maze_table, state.position, going_up)?, None)
+        },
+        Move::Teleport => {
+            // teleporter pads hold no keys, and the jump itself passes no door
+            let partner_position = maze_table[state.position].teleport_to?;
+            let mut neighbour_state = state.clone();
+            neighbour_state.position = partner_position;
+            return finish_move(maze_table, maze_index, SearchNode {
+                state: neighbour_state,
+                parent: Some(parent_index),
+                last_move: Some(next_move.clone()),
+                key_action: None,
+                opened_door: None,
+                move_cost: maze_table[partner_position].cost as u64,
+                teleported_from: Some(state.position),
+                toggled_switch: false,
+            });
+        },
+        Move::Pull => {
+            let Some(Switch::Lever(group)) = maze_table[state.position].switch else { return None };
+            let mut neighbour_state = state.clone();
+            toggle_bit(&mut neighbour_state.toggled_gates, group);
+            return finish_move(maze_table, maze_index, SearchNode {
+                state: neighbour_state,
+                parent: Some(parent_index),
+                last_move: Some(next_move.clone()),
+                key_action: None,
+                opened_door: None,
+                move_cost: maze_table[state.position].cost as u64,
+                teleported_from: None,
+                toggled_switch: true,
+            });
+        },
+        Move::Wait => {
+            return finish_move(maze_table, maze_index, SearchNode {
+                state: state.clone(),
+                parent: Some(parent_index),
+                last_move: Some(next_move.clone()),
+                key_action: None,
+                opened_door: None,
+                move_cost: maze_table[state.position].cost as u64,
+                teleported_from: None,
+                toggled_switch: false,
+            });
+        },
+        Move::DropKey(color, uses) => {
+            let color = *color;
+            let index = state.keys_carried.binary_search(&(color, *uses)).ok()?;
+            let mut neighbour_state = state.clone();
+            neighbour_state.keys_carried.remove(index);
+            // the key takes its own openings with it, a reusable key is good for the color as long as one is left
+            neighbour_state.key_uses_left[color] = match maze_index.key_rule {
+                KeyRule::Reusable => neighbour_state.keys_carried.iter().any(|(key_color, _)| *key_color == color) as u32,
+                _ => state.key_uses_left[color] - uses,
+            };
+            let dropped_key = (state.position, color, *uses);
+            let index = neighbour_state.dropped_keys.binary_search(&dropped_key).unwrap_or_else(|index| index);
+            neighbour_state.dropped_keys.insert(index, dropped_key);
+            return finish_move(maze_table, maze_index, SearchNode {
+                state: neighbour_state,
+                parent: Some(parent_index),
+                last_move: Some(next_move.clone()),
+                key_action: Some(KeyAction::Drop(color)),
+                opened_door: None,
+                move_cost: maze_table[state.position].cost as u64,
+                teleported_from: None,
+                toggled_switch: false,
+            });
+        },
+        Move::PickUpKey(color) => {
+            let color = *color;
+            if !maze_index.has_room(state) {
+                return None;
+            }
+            let mut neighbour_state = state.clone();
+            // a key left lying for lack of room comes first, then the dropped key with the most openings left
+            let left_key = maze_index.key_ids[state.position].filter(|key_id| is_bit_set(&state.keys_left, *key_id) && maze_index.key_colors[*key_id] == color);
+            if let Some(key_id) = left_key {
+                clear_bit(&mut neighbour_state.keys_left, key_id);
+                maze_index.take_key(&mut neighbour_state, color, maze_index.key_uses(1));
+            }
+            else {
+                let index = state.dropped_keys.iter().rposition(|(position, key_color, _)| *position == state.position && *key_color == color)?;
+                let (_, _, uses) = neighbour_state.dropped_keys.remove(index);
+                maze_index.take_key(&mut neighbour_state, color, uses);
+            }
+            return finish_move(maze_table, maze_index, SearchNode {
+                state: neighbour_state,
+                parent: Some(parent_index),
+                last_move: Some(next_move.clone()),
+                key_action: Some(KeyAction::PickUp(color)),
+                opened_door: None,
+                move_cost: maze_table[state.position].cost as u64 + maze_index.action_costs.pickup as u64,
+                teleported_from: None,
+                toggled_switch: false,
+            });
+        },
+    };
+
+    // a gate is open if it started open and its group was toggled an even number of times, or the other way round
+    if let Some((group, open_at_start)) = direction.and_then(|direction| maze_index.gates[state.position][direction.index()]) {
+        if open_at_start == is_bit_set(&state.toggled_gates, group) {
+            return None;
+        }
+    }
+
+    // stepping on a forced teleporter pad ends the move on its partner, but a door leads to the pad
+    let door_position = neighbour_position;
+    let mut teleported_from = None;
+    if maze_index.teleport_rule == TeleportRule::Forced {
+        if let Some(partner_position) = maze_table[neighbour_position].teleport_to {
+            teleported_from = Some(neighbour_position);
+            neighbour_position = partner_position;
+        }
+    }
+
+    let mut neighbour_state = state.clone();
+    neighbour_state.position = neighbour_position;
+
+    // check door and unlock (from both sides) if needed, stairs have no doors
+    let mut opened_door = None;
+    let door = direction.and_then(|direction| maze_index.door_ids[state.position][direction.index()].map(|door_id| (door_id, direction.index())));
+    if let Some((door_id, side)) = door {
+        if is_bit_set(&state.locked_doors, door_id) {
+            // door can only be unlocked from the other side
+            if maze_index.sealed_doors[state.position][side] {
+                return None;
+            }
+            // no available keys of the door color, so neighbour is not valid
+            let color = maze_index.door_colors[door_id];
+            if state.key_uses_left[color] == 0 {
+                return None;
+            }
+            // use up the key (unless keys are reusable) and unlock door for next state, keys wear out one
+            // after another, so the most worn key of the color is used and gone once it has no openings left
+            if maze_index.key_rule.uses_per_key().is_some() {
+                neighbour_state.key_uses_left[color] -= 1;
+                if maze_index.key_capacity.is_some() {
+                    let index = neighbour_state.keys_carried.iter().position(|(key_color, _)| *key_color == color).unwrap();
+                    neighbour_state.keys_carried[index].1 -= 1;
+                    if neighbour_state.keys_carried[index].1 == 0 {
+                        neighbour_state.keys_carried.remove(index);
+                    }
+                }
+            }
+            clear_bit(&mut neighbour_state.locked_doors, door_id);
+            opened_door = Some(door_position);
+        }
+    }
+
+    // pick up key in neighbour cell if available, a full player leaves it lying
+    let mut key_action = None;
+    if let Some(key_id) = maze_index.key_ids[neighbour_position] {
+        if is_bit_set(&state.keys_left, key_id) && maze_index.has_room(&neighbour_state) {
+            let color = maze_index.key_colors[key_id];
+            maze_index.take_key(&mut neighbour_state, color, maze_index.key_uses(1));
+            clear_bit(&mut neighbour_state.keys_left, key_id);
+            key_action = Some(KeyAction::PickUp(color));
+        }
+    }
+
+    // stepping on a pressure plate toggles its gates
+    let mut toggled_switch = false;
+    if let Some(Switch::Plate(group)) = maze_table[neighbour_position].switch {
+        toggle_bit(&mut neighbour_state.toggled_gates, group);
+        toggled_switch = true;
+    }
+
+    let mut move_cost = maze_table[neighbour_position].cost as u64;
+    if opened_door.is_some() {
+        move_cost += maze_index.action_costs.unlock as u64;
+    }
+    if key_action.is_some() {
+        move_cost += maze_index.action_costs.pickup as u64;
+    }
+
+    return finish_move(maze_table, maze_index, SearchNode {
+        state: neighbour_state,
+        parent: Some(parent_index),
+        last_move: Some(next_move.clone()),
+        key_action,
+        opened_door,
+        move_cost,
+        teleported_from,
+        toggled_switch,
+    });
+}
+
+// moves worth trying in the state, get_successor decides if they are possible
+fn get_next_moves(maze_table: &Array2D<MazeCell>, maze_index: &MazeIndex, state: &SearchState) -> Vec<Move> {
+    let current_cell = &maze_table[state.position];
+    let mut next_moves: Vec<Move> = current_cell.available_directions.iter().map(|direction| Move::Step(direction.clone())).collect();
+    if maze_index.teleport_rule == TeleportRule::Optional {
+        next_moves.push(Move::Teleport);
+    }
+    if current_cell.stairs_up {
+        next_moves.push(Move::Up);
+    }
+    if current_cell.stairs_down {
+        next_moves.push(Move::Down);
+    }
+    if let Some(Switch::Lever(_)) = current_cell.switch {
+        next_moves.push(Move::Pull);
+    }
+    // keys only change hands outside of stepping on them when there's a limit on how many can be carried
+    if maze_index.key_capacity.is_some() {
+        // keys of one color with the same openings left are interchangeable, but a worn and a fresh one aren't
+        if maze_index.capacity_can_bind(state) {
+            let mut keys_carried = state.keys_carried.clone();
+            keys_carried.dedup();
+            next_moves.extend(keys_carried.into_iter().map(|(color, uses)| Move::DropKey(color, uses)));
+        }
+        let mut colors_lying: Vec<usize> = state.dropped_keys.iter()
+            .filter(|(position, _, _)| *position == state.position)
+            .map(|(_, color, _)| *color)
+            .collect();
+        if let Some(key_id) = maze_index.key_ids[state.position].filter(|key_id| is_bit_set(&state.keys_left, *key_id)) {
+            colors_lying.push(maze_index.key_colors[key_id]);
+        }
+        colors_lying.sort_unstable();
+        colors_lying.dedup();
+        next_moves.extend(colors_lying.into_iter().map(Move::PickUpKey));
+    }
+    // standing still only helps while waiting for a hazard to clear, and is tried first so paths
+    // of the same length wait instead of walking back and forth
+    if maze_index.hazard_cycle > 1 {
+        next_moves.insert(0, Move::Wait);
+    }
+    return next_moves;
+}
+
+fn get_valid_neighbours(maze_table: &Array2D<MazeCell>, maze_index: &MazeIndex, parent_index: usize, state: &SearchState) -> Vec<SearchNode> {
+    return get_next_moves(maze_table, maze_index, state).iter()
+        .filter_map(|next_move| get_successor(maze_table, maze_index, parent_index, state, next_move))
+        .collect();
+}
+
+// what every strategy searches: the start, the shared successor function and the goal test
+pub struct SearchProblem<'a> {
+    maze_table: &'a Array2D<MazeCell>,
+    maze_index: MazeIndex,
+    initial_state: SearchState,
+}
+
+impl<'a> SearchProblem<'a> {
+    pub fn new(maze_table: &'a Array2D<MazeCell>, initial_maze_state: &MazeState) -> Self {
+        let maze_index = MazeIndex::new(maze_table, initial_maze_state);
+        let initial_state = maze_index.initial_state(initial_maze_state);
+        Self {
+            maze_table,
+            maze_index,
+            initial_state,
+        }
+    }
+
+    pub fn maze_table(&self) -> &'a Array2D<MazeCell> {
+        self.maze_table
+    }
+
+    pub fn initial_node(&self) -> SearchNode {
+        SearchNode { state: self.initial_state.clone(), parent: None, last_move: None, key_action: None, opened_door: None, move_cost: 0, teleported_from: None, toggled_switch: false }
+    }
+
+    // every state one move away, as arena nodes pointing back to parent_index
+    pub fn successors(&self, parent_index: usize, state: &SearchState) -> Vec<SearchNode> {
+        get_valid_neighbours(self.maze_table, &self.maze_index, parent_index, state)
+    }
+
+    // moves worth trying from the state, some of them may turn out to be blocked
+    pub fn moves(&self, state: &SearchState) -> Vec<Move> {
+        get_next_moves(self.maze_table, &self.maze_index, state)
+    }
+
+    // the state a single move leads to, or None if the move isn't possible
+    pub fn successor(&self, parent_index: usize, state: &SearchState, next_move: &Move) -> Option<SearchNode> {
+        get_successor(self.maze_table, &self.maze_index, parent_index, state, next_move)
+    }
+
+    pub fn keys_held(&self, state: &SearchState) -> u32 {
+        self.maze_index.keys_held(state)
+    }
+
+    // next tick of the hazard cycle
+    pub fn next_tick(&self, tick: u64) -> u64 {
+        (tick + 1) % self.maze_index.hazard_cycle
+    }
+
+    pub fn is_goal(&self, state: &SearchState) -> bool {
+        self.maze_table[state.position].end_of_maze
+    }
+
+    // lets go of the table, keeping the index for another table of the same maze
+    pub fn detach(self) -> DetachedProblem {
+        DetachedProblem { maze_index: self.maze_index, initial_state: self.initial_state }
+    }
+}
+
+// the index of a search problem without its table, so that cells can change between searches without rebuilding
+// the index, as long as the keys, doors and teleporters stay where they are
+pub struct DetachedProblem {
+    maze_index: MazeIndex,
+    initial_state: SearchState,
+}
+
+impl DetachedProblem {
+    pub fn attach(self, maze_table: &Array2D<MazeCell>) -> SearchProblem<'_> {
+        SearchProblem { maze_table, maze_index: self.maze_index, initial_state: self.initial_state }
+    }
+
+    // adds a gate the problem didn't know about when it was built
+    pub fn add_gate(&mut self, maze_table: &Array2D<MazeCell>, gate: &(usize, usize, Direction), group: usize, open: bool) {
+        self.maze_index.add_gate(maze_table, gate, group, open);
+    }
+}
+
+pub fn solve_maze_bfs(maze_table: &Array2D<MazeCell>, initial_maze_state: &MazeState) -> Result<Solution, Unsolvable> {
+    let now = Instant::now();
+    let mut states_expanded = 0;
+
+    let problem = SearchProblem::new(maze_table, initial_maze_state);
+    let initial_node = problem.initial_node();
+
+    // full logical states already reached
+    let mut visited: HashSet<SearchState> = HashSet::new();
+    visited.insert(initial_node.state.clone());
+
+    let mut search_nodes: Vec<SearchNode> = vec![initial_node];
+    let mut bfs_queue: VecDeque<usize> = VecDeque::new();
+    bfs_queue.push_back(0);
+
+    while let Some(node_index) = bfs_queue.pop_front() {
+        // found end of maze
+        if problem.is_goal(&search_nodes[node_index].state) {
+            return Ok(Solution::new(&problem, &search_nodes, node_index, states_expanded, now.elapsed()));
+        }
+        states_expanded += 1;
+
+        for neighbour_node in problem.successors(node_index, &search_nodes[node_index].state) {
+            // ignore neighbour if the same position, keys and doors have been reached before
+            if visited.insert(neighbour_node.state.clone()) {
+                bfs_queue.push_back(search_nodes.len());
+                search_nodes.push(neighbour_node);
+            }
+        }
+    }
+
+    return Err(Unsolvable { states_expanded, elapsed: now.elapsed() });
+}
+
+const NUM_VISITED_SHARDS: usize = 64;
+
+pub fn default_num_threads() -> usize {
+    return thread::available_parallelism().map(|num_threads| num_threads.get()).unwrap_or(4);
+}
+
+// visited set split into separately locked shards, so workers rarely wait on each other
+struct ShardedVisited {
+    shards: Vec<Mutex<HashSet<SearchState>>>,
+}
+
+impl ShardedVisited {
+    fn new() -> Self {
+        Self {
+            shards: (0..NUM_VISITED_SHARDS).map(|_| Mutex::new(HashSet::new())).collect(),
+        }
+    }
+
+    // returns true if the state wasn't visited before
+    fn insert(&self, state: &SearchState) -> bool {
+        let mut hasher = DefaultHasher::new();
+        state.hash(&mut hasher);
+        let shard = &self.shards[hasher.finish() as usize % NUM_VISITED_SHARDS];
+        return shard.lock().unwrap().insert(state.clone());
+    }
+}
+
+pub fn solve_maze_bfs_parallel(maze_table: &Array2D<MazeCell>, initial_maze_state: &MazeState, num_threads: usize) -> Result<Solution, Unsolvable> {
+    let now = Instant::now();
+    let mut states_expanded = 0;
+    let num_threads = num_threads.max(1);
+
+    let problem = SearchProblem::new(maze_table, initial_maze_state);
+    let initial_node = problem.initial_node();
+
+    // full logical states already reached
+    let visited = ShardedVisited::new();
+    visited.insert(&initial_node.state);
+
+    let mut search_nodes: Vec<SearchNode> = vec![initial_node];
+    let mut frontier: Vec<usize> = vec![0];
+
+    thread::scope(|scope| {
+        // workers live for the whole search and expand one partition of every frontier
+        let (result_tx, result_rx) = mpsc::channel::<(usize, Vec<SearchNode>)>();
+        let mut job_senders = Vec::new();
+        for _ in 0..num_threads {
+            let (job_tx, job_rx) = mpsc::channel::<(usize, Vec<(usize, SearchState)>)>();
+            job_senders.push(job_tx);
+            let result_tx = result_tx.clone();
+            let problem = &problem;
+            let visited = &visited;
+            scope.spawn(move || {
+                for (partition_index, partition) in job_rx {
+                    let mut neighbour_nodes: Vec<SearchNode> = Vec::new();
+                    for (node_index, state) in partition.iter() {
+                        for neighbour_node in problem.successors(*node_index, state) {
+                            // ignore neighbour if the same position, keys and doors have been reached before
+                            if visited.insert(&neighbour_node.state) {
+                                neighbour_nodes.push(neighbour_node);
+                            }
+                        }
+                    }
+                    result_tx.send((partition_index, neighbour_nodes)).unwrap();
+                }
+            });
+        }
+
+        while !frontier.is_empty() {
+            // found end of maze, checked in queue order so the path matches the sequential one in length
+            if let Some(node_index) = frontier.iter().find(|node_index| problem.is_goal(&search_nodes[**node_index].state)) {
+                return Ok(Solution::new(&problem, &search_nodes, *node_index, states_expanded, now.elapsed()));
+            }
+            states_expanded += frontier.len();
+
+            let partition_size = frontier.len().div_ceil(num_threads);
+            let mut num_partitions = 0;
+            for (partition_index, partition) in frontier.chunks(partition_size).enumerate() {
+                let partition: Vec<(usize, SearchState)> = partition.iter()
+                    .map(|node_index| (*node_index, search_nodes[*node_index].state.clone()))
+                    .collect();
+                job_senders[partition_index].send((partition_index, partition)).unwrap();
+                num_partitions += 1;
+            }
+
+            // next frontier keeps the partition order
+            let mut results: Vec<(usize, Vec<SearchNode>)> = result_rx.iter().take(num_partitions).collect();
+            results.sort_by_key(|(partition_index, _)| *partition_index);
+            frontier = Vec::new();
+            for (_, neighbour_nodes) in results {
+                for neighbour_node in neighbour_nodes {
+                    frontier.push(search_nodes.len());
+                    search_nodes.push(neighbour_node);
+                }
+            }
+        }
+
+        // dropping the job senders stops the workers
+        return Err(Unsolvable { states_expanded, elapsed: now.elapsed() });
+    })
+}
+
+const MAX_BATCH_SIZE: usize = 256;
+
+pub fn solve_maze_bfs_parallel_channels(maze_table: &Array2D<MazeCell>, initial_maze_state: &MazeState, num_threads: usize) -> Result<Solution, Unsolvable> {
+    let now = Instant::now();
+    let mut states_expanded = 0;
+    let num_threads = num_threads.max(1);
+
+    let problem = SearchProblem::new(maze_table, initial_maze_state);
+    let initial_node = problem.initial_node();
+
+    // found end of maze at the start
+    if problem.is_goal(&initial_node.state) {
+        let search_nodes = vec![initial_node];
+        return Ok(Solution::new(&problem, &search_nodes, 0, states_expanded, now.elapsed()));
+    }
+
+    // full logical states already reached, with the arena node that holds them
+    let mut visited: HashMap<SearchState, usize> = HashMap::new();
+    visited.insert(initial_node.state.clone(), 0);
+
+    let mut search_nodes: Vec<SearchNode> = vec![initial_node];
+    let mut depths: Vec<usize> = vec![0];
+    let mut queued: Vec<bool> = vec![true];
+    let mut bfs_queue: VecDeque<usize> = VecDeque::new();
+    bfs_queue.push_back(0);
+    let mut maze_end_node: Option<usize> = None;
+
+    let (batch_tx, batch_rx) = mpsc::channel::<Vec<(usize, SearchState)>>();
+    let (successor_tx, successor_rx) = mpsc::channel::<Vec<SearchNode>>();
+    let batch_rx = Mutex::new(batch_rx);
+
+    thread::scope(|scope| {
+
+        // expanders only compute successors, the coordinator below owns everything else
+        for _ in 0..num_threads {
+            let batch_rx = &batch_rx;
+            let successor_tx = successor_tx.clone();
+            let problem = &problem;
+            scope.spawn(move || {
+                loop {
+                    // the lock is only held while waiting for the next batch
+                    let batch = match batch_rx.lock().unwrap().recv() {
+                        Ok(batch) => batch,
+                        Err(_) => break,
+                    };
+                    let mut successors: Vec<SearchNode> = Vec::new();
+                    for (node_index, state) in &batch {
+                        successors.extend(problem.successors(*node_index, state));
+                    }
+                    successor_tx.send(successors).unwrap();
+                }
+            });
+        }
+
+        let mut batches_in_flight = 0;
+        loop {
+            // keep every expander busy with a batch and one more waiting
+            while batches_in_flight < 2 * num_threads {
+                let batch_size = (bfs_queue.len() / num_threads).clamp(1, MAX_BATCH_SIZE);
+                let mut batch: Vec<(usize, SearchState)> = Vec::new();
+                while batch.len() < batch_size {
+                    let Some(node_index) = bfs_queue.pop_front() else { break };
+                    queued[node_index] = false;
+                    // states that can't lead to a shorter path than the one found are not expanded
+                    if let Some(end_node_index) = maze_end_node {
+                        if depths[node_index] + 1 >= depths[end_node_index] {
+                            continue;
+                        }
+                    }
+                    batch.push((node_index, search_nodes[node_index].state.clone()));
+                }
+                if batch.is_empty() {
+                    break;
+                }
+                states_expanded += batch.len();
+                batch_tx.send(batch).unwrap();
+                batches_in_flight += 1;
+            }
+
+            // solution found or frontier exhausted, and no batch is still being expanded
+            if batches_in_flight == 0 {
+                break;
+            }
+
+            let successors = successor_rx.recv().unwrap();
+            batches_in_flight -= 1;
+            for successor in successors {
+                let depth = depths[successor.parent.unwrap()] + 1;
+                let node_index = match visited.get(&successor.state) {
+                    // batches finish out of order, so a state can be reached again over a shorter path
+                    Some(&node_index) if depth < depths[node_index] => {
+                        search_nodes[node_index] = successor;
+                        depths[node_index] = depth;
+                        node_index
+                    },
+                    Some(_) => continue,
+                    None => {
+                        let node_index = search_nodes.len();
+                        visited.insert(successor.state.clone(), node_index);
+                        search_nodes.push(successor);
+                        depths.push(depth);
+                        queued.push(false);
+                        node_index
+                    }
+                };
+
+                // found end of maze
+                if problem.is_goal(&search_nodes[node_index].state) {
+                    if maze_end_node.is_none_or(|end_node_index| depth < depths[end_node_index] || end_node_index == node_index) {
+                        maze_end_node = Some(node_index);
+                    }
+                }
+                else if !queued[node_index] {
+                    queued[node_index] = true;
+                    bfs_queue.push_back(node_index);
+                }
+            }
+        }
+
+        // closing the batch channel stops the expanders
+        drop(batch_tx);
+    });
+
+    return match maze_end_node {
+        Some(end_node_index) => Ok(Solution::new(&problem, &search_nodes, end_node_index, states_expanded, now.elapsed())),
+        None => Err(Unsolvable { states_expanded, elapsed: now.elapsed() }),
+    };
+}
@@ -0,0 +1,127 @@
+use array2d::Array2D;
+
+use crate::astar::{ solve_maze_astar, Heuristic };
+use crate::dijkstra::solve_maze_dijkstra;
+use crate::maze::{ MazeCell, MazeState };
+use crate::solve::{ solve_maze_bfs, solve_maze_bfs_parallel, solve_maze_bfs_parallel_channels, Solution, Unsolvable };
+
+// a search strategy, usually built on top of solve::SearchProblem
+pub trait Solver: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn solve(&self, maze_table: &Array2D<MazeCell>, initial_maze_state: &MazeState) -> Result<Solution, Unsolvable>;
+}
+
+pub struct SequentialBfs;
+
+impl Solver for SequentialBfs {
+    fn name(&self) -> &str {
+        "sequential"
+    }
+
+    fn solve(&self, maze_table: &Array2D<MazeCell>, initial_maze_state: &MazeState) -> Result<Solution, Unsolvable> {
+        solve_maze_bfs(maze_table, initial_maze_state)
+    }
+}
+
+pub struct ParallelBfs {
+    pub num_threads: usize,
+}
+
+impl Solver for ParallelBfs {
+    fn name(&self) -> &str {
+        "parallel"
+    }
+
+    fn solve(&self, maze_table: &Array2D<MazeCell>, initial_maze_state: &MazeState) -> Result<Solution, Unsolvable> {
+        solve_maze_bfs_parallel(maze_table, initial_maze_state, self.num_threads)
+    }
+}
+
+pub struct ChannelsBfs {
+    pub num_threads: usize,
+}
+
+impl Solver for ChannelsBfs {
+    fn name(&self) -> &str {
+        "channels"
+    }
+
+    fn solve(&self, maze_table: &Array2D<MazeCell>, initial_maze_state: &MazeState) -> Result<Solution, Unsolvable> {
+        solve_maze_bfs_parallel_channels(maze_table, initial_maze_state, self.num_threads)
+    }
+}
+
+pub struct AStar {
+    pub heuristic: Heuristic,
+}
+
+impl Solver for AStar {
+    fn name(&self) -> &str {
+        match self.heuristic {
+            Heuristic::Manhattan => "astar",
+            Heuristic::RelaxedDistance => "astar-relaxed",
+        }
+    }
+
+    fn solve(&self, maze_table: &Array2D<MazeCell>, initial_maze_state: &MazeState) -> Result<Solution, Unsolvable> {
+        solve_maze_astar(maze_table, initial_maze_state, self.heuristic)
+    }
+}
+
+pub struct Dijkstra;
+
+impl Solver for Dijkstra {
+    fn name(&self) -> &str {
+        "dijkstra"
+    }
+
+    fn solve(&self, maze_table: &Array2D<MazeCell>, initial_maze_state: &MazeState) -> Result<Solution, Unsolvable> {
+        solve_maze_dijkstra(maze_table, initial_maze_state)
+    }
+}
+
+// solvers by name, in registration order
+#[derive(Default)]
+pub struct SolverRegistry {
+    solvers: Vec<Box<dyn Solver>>,
+}
+
+impl SolverRegistry {
+    pub fn new() -> Self {
+        Self {
+            solvers: Vec::new(),
+        }
+    }
+
+    pub fn with_builtin_solvers(num_threads: usize) -> Self {
+        let mut registry = Self::new();
+        registry.register(Box::new(SequentialBfs));
+        registry.register(Box::new(ParallelBfs { num_threads }));
+        registry.register(Box::new(ChannelsBfs { num_threads }));
+        registry.register(Box::new(AStar { heuristic: Heuristic::Manhattan }));
+        registry.register(Box::new(AStar { heuristic: Heuristic::RelaxedDistance }));
+        registry.register(Box::new(Dijkstra));
+        return registry;
+    }
+
+    // a solver with an already registered name replaces the old one
+    pub fn register(&mut self, solver: Box<dyn Solver>) {
+        match self.solvers.iter().position(|registered| registered.name() == solver.name()) {
+            Some(index) => self.solvers[index] = solver,
+            None => self.solvers.push(solver),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Solver> {
+        self.solvers.iter().find(|solver| solver.name() == name).map(|solver| solver.as_ref())
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.solvers.iter().map(|solver| solver.name()).collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Solver> {
+        self.solvers.iter().map(|solver| solver.as_ref())
+    }
+}
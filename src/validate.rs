@@ -0,0 +1,357 @@
+use array2d::Array2D;
+use std::collections::{ HashSet, VecDeque };
+use std::fmt;
+
+use crate::maze::{ cell_on_next_floor, floor_position, format_position, hazard_cycle, neighbour_in_direction, table_position, Direction, MazeCell, MazeState, Move, Position, ALL_DIRECTIONS };
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum MazeIssue {
+    BoundaryOpening { position: Position, direction: Direction },
+    AsymmetricWall { position: Position, direction: Direction },
+    AsymmetricDoor { position: Position, direction: Direction },
+    DoorWithoutPassage { position: Position, direction: Direction },
+    StairsOutOfMaze { position: Position, next_move: Move },
+    AsymmetricStairs { position: Position, next_move: Move },
+    OneWayOpensBack { position: Position, direction: Direction },
+    DoorSealedFromBothSides { position: Position, direction: Direction },
+    MismatchedDoorColor { position: Position, direction: Direction, color: String, neighbour_color: String },
+    MismatchedGate { position: Position, direction: Direction, gate: String, neighbour_gate: String },
+    GateWithoutSwitch { group: String },
+    HazardCycleTooLong { cycle: u64 },
+    SwitchWithoutGate { group: String },
+    UnreachableKey { position: Position },
+    UnreachableExit { position: Position },
+    NoExit,
+}
+
+impl MazeIssue {
+    pub fn display<'a>(&'a self, maze_table: &'a Array2D<MazeCell>) -> IssueDisplay<'a> {
+        IssueDisplay { issue: self, maze_table }
+    }
+
+    // unreachable keys and exits, doors nobody can open and gate groups without both gates and switches don't break the solvers, everything else does
+    pub fn is_error(&self) -> bool {
+        !matches!(self, MazeIssue::UnreachableKey { .. } | MazeIssue::UnreachableExit { .. } | MazeIssue::DoorSealedFromBothSides { .. }
+            | MazeIssue::GateWithoutSwitch { .. } | MazeIssue::SwitchWithoutGate { .. })
+    }
+}
+
+// an issue with its positions written like in the rest of the output, "(level, row, col)" in a maze with several floors
+pub struct IssueDisplay<'a> {
+    issue: &'a MazeIssue,
+    maze_table: &'a Array2D<MazeCell>,
+}
+
+impl fmt::Display for IssueDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cell = |position: &Position| format_position(self.maze_table, table_position(self.maze_table, *position));
+        match self.issue {
+            MazeIssue::BoundaryOpening { position, direction } =>
+                write!(f, "cell {} opens {:?} out of the maze", cell(position), direction),
+            MazeIssue::AsymmetricWall { position, direction } =>
+                write!(f, "cell {} opens {:?}, but its neighbour doesn't open {:?}", cell(position), direction, direction.opposite()),
+            MazeIssue::AsymmetricDoor { position, direction } =>
+                write!(f, "cell {} has a door {:?}, but its neighbour has no door {:?}", cell(position), direction, direction.opposite()),
+            MazeIssue::DoorWithoutPassage { position, direction } =>
+                write!(f, "cell {} has a door {:?}, but no passage in that direction", cell(position), direction),
+            MazeIssue::StairsOutOfMaze { position, next_move } =>
+                write!(f, "cell {} has stairs {}, but there is no floor there", cell(position), stairs_name(next_move)),
+            MazeIssue::AsymmetricStairs { position, next_move } =>
+                write!(f, "cell {} has stairs {}, but the cell they lead to has no stairs back", cell(position), stairs_name(next_move)),
+            MazeIssue::OneWayOpensBack { position, direction } =>
+                write!(f, "cell {} has a one-way passage {:?}, but its neighbour opens {:?}", cell(position), direction, direction.opposite()),
+            MazeIssue::DoorSealedFromBothSides { position, direction } =>
+                write!(f, "door {:?} of cell {} is sealed from both sides and can never be unlocked", direction, cell(position)),
+            MazeIssue::MismatchedDoorColor { position, direction, color, neighbour_color } =>
+                write!(f, "cell {} has a {} door {:?}, but its neighbour has a {} door {:?}",
+                    cell(position), color, direction, neighbour_color, direction.opposite()),
+            MazeIssue::MismatchedGate { position, direction, gate, neighbour_gate } =>
+                write!(f, "cell {} has a {} on its {:?} side, but its neighbour has a {} on its {:?} side",
+                    cell(position), gate, direction, neighbour_gate, direction.opposite()),
+            MazeIssue::HazardCycleTooLong { cycle } =>
+                write!(f, "hazards only repeat every {} ticks, the search allows at most {}", cycle, MAX_HAZARD_CYCLE),
+            MazeIssue::GateWithoutSwitch { group } => write!(f, "gates of group \"{}\" never change, no plate or lever toggles them", group),
+            MazeIssue::SwitchWithoutGate { group } => write!(f, "switches of group \"{}\" don't toggle any gate", group),
+            MazeIssue::UnreachableKey { position } =>
+                write!(f, "key at {} can't be reached from the start", cell(position)),
+            MazeIssue::UnreachableExit { position } =>
+                write!(f, "exit at {} can't be reached from the start", cell(position)),
+            MazeIssue::NoExit => write!(f, "maze has no exit"),
+        }
+    }
+}
+
+// the time-expanded search keeps a copy of every state for every tick of the hazard cycle
+pub const MAX_HAZARD_CYCLE: u64 = 10_000;
+
+fn stairs_name(next_move: &Move) -> &'static str {
+    if *next_move == Move::Up { "up" } else { "down" }
+}
+
+// e.g. "closed gate of group a"
+fn gate_description(maze_state: &MazeState, gate: &(usize, usize, Direction)) -> String {
+    let state = if maze_state.open_gates.contains(gate) { "open" } else { "closed" };
+    return format!("{} gate of group {}", state, maze_state.gate_names[maze_state.gates[gate]]);
+}
+
+// cells reachable from the start when every door and gate is treated as open, teleporters and stairs included
+pub fn find_reachable_cells(maze_table: &Array2D<MazeCell>, start_position: (usize, usize)) -> HashSet<(usize, usize)> {
+    let mut reachable: HashSet<(usize, usize)> = HashSet::new();
+    reachable.insert(start_position);
+
+    let mut queue: VecDeque<(usize, usize)> = VecDeque::new();
+    queue.push_back(start_position);
+
+    while let Some(position) = queue.pop_front() {
+        let maze_cell = maze_table.get(position.0, position.1).unwrap();
+        let mut neighbour_positions: Vec<(usize, usize)> = maze_cell.available_directions.iter()
+            .filter_map(|direction| neighbour_in_direction(maze_table, position, direction))
+            .collect();
+        neighbour_positions.extend(maze_cell.teleport_to);
+        if maze_cell.stairs_up {
+            neighbour_positions.extend(cell_on_next_floor(maze_table, position, true));
+        }
+        if maze_cell.stairs_down {
+            neighbour_positions.extend(cell_on_next_floor(maze_table, position, false));
+        }
+        for neighbour_position in neighbour_positions {
+            if reachable.insert(neighbour_position) {
+                queue.push_back(neighbour_position);
+            }
+        }
+    }
+
+    return reachable;
+}
+
+pub fn validate_maze(maze_table: &Array2D<MazeCell>, maze_state: &MazeState) -> Vec<MazeIssue> {
+    let mut issues: Vec<MazeIssue> = Vec::new();
+
+    // door sides are looked up once per cell side, so they go into sets instead of being searched every time
+    let locked_doors: HashSet<&(usize, usize, Direction)> = maze_state.cells_with_locked_doors.iter().collect();
+    let sealed_doors: HashSet<&(usize, usize, Direction)> = maze_state.sealed_doors.iter().collect();
+
+    for maze_cell in maze_table.elements_row_major_iter() {
+        let position = (maze_cell.row_index, maze_cell.col_index);
+        let cell_position = floor_position(maze_table, position);
+        for direction in &ALL_DIRECTIONS {
+            let has_passage = maze_cell.available_directions.contains(direction);
+            let has_door = locked_doors.contains(&(position.0, position.1, direction.clone()));
+
+            match neighbour_in_direction(maze_table, position, direction) {
+                None => {
+                    if has_passage {
+                        issues.push(MazeIssue::BoundaryOpening { position: cell_position, direction: direction.clone() });
+                    }
+                },
+                Some(neighbour_position) => {
+                    let neighbour_cell = maze_table.get(neighbour_position.0, neighbour_position.1).unwrap();
+                    let opposite_direction = direction.opposite();
+                    let neighbour_opens_back = neighbour_cell.available_directions.contains(&opposite_direction);
+                    // one-way passages are meant to be asymmetric, so is their door
+                    let is_one_way = maze_cell.one_way.contains(direction);
+                    if is_one_way && neighbour_opens_back {
+                        issues.push(MazeIssue::OneWayOpensBack { position: cell_position, direction: direction.clone() });
+                    }
+                    if has_passage && !is_one_way && !neighbour_opens_back {
+                        issues.push(MazeIssue::AsymmetricWall { position: cell_position, direction: direction.clone() });
+                    }
+                    let neighbour_has_door = locked_doors.contains(&(neighbour_position.0, neighbour_position.1, opposite_direction.clone()));
+                    if has_door && !is_one_way && !neighbour_has_door {
+                        issues.push(MazeIssue::AsymmetricDoor { position: cell_position, direction: direction.clone() });
+                    }
+                    let is_sealed = sealed_doors.contains(&(position.0, position.1, direction.clone()));
+                    let neighbour_is_sealed = sealed_doors.contains(&(neighbour_position.0, neighbour_position.1, opposite_direction.clone()));
+                    if is_sealed && (is_one_way || (neighbour_is_sealed && (*direction == Direction::EAST || *direction == Direction::SOUTH))) {
+                        issues.push(MazeIssue::DoorSealedFromBothSides { position: cell_position, direction: direction.clone() });
+                    }
+                    // both sides of a door are compared once, from the west and north cell
+                    let color = maze_state.door_color(position, direction);
+                    let neighbour_color = maze_state.door_color(neighbour_position, &opposite_direction);
+                    if has_door && neighbour_has_door && color != neighbour_color && (*direction == Direction::EAST || *direction == Direction::SOUTH) {
+                        issues.push(MazeIssue::MismatchedDoorColor {
+                            position: cell_position,
+                            direction: direction.clone(),
+                            color: maze_state.color_names[color].clone(),
+                            neighbour_color: maze_state.color_names[neighbour_color].clone()
+                        });
+                    }
+                    // a gate only needs to be declared on one side, but both sides have to agree if it's declared on both
+                    let gate = (position.0, position.1, direction.clone());
+                    let neighbour_gate = (neighbour_position.0, neighbour_position.1, opposite_direction.clone());
+                    if maze_state.gates.contains_key(&gate) && maze_state.gates.contains_key(&neighbour_gate) && (*direction == Direction::EAST || *direction == Direction::SOUTH) {
+                        let description = gate_description(maze_state, &gate);
+                        let neighbour_description = gate_description(maze_state, &neighbour_gate);
+                        if description != neighbour_description {
+                            issues.push(MazeIssue::MismatchedGate { position: cell_position, direction: direction.clone(), gate: description, neighbour_gate: neighbour_description });
+                        }
+                    }
+                },
+            }
+
+            if has_door && !has_passage {
+                issues.push(MazeIssue::DoorWithoutPassage { position: cell_position, direction: direction.clone() });
+            }
+        }
+    }
+
+    // stairs lead to the same cell of the next floor, which has to lead back
+    for maze_cell in maze_table.elements_row_major_iter() {
+        let position = (maze_cell.row_index, maze_cell.col_index);
+        let cell_position = floor_position(maze_table, position);
+        for (has_stairs, next_move) in [(maze_cell.stairs_up, Move::Up), (maze_cell.stairs_down, Move::Down)] {
+            if !has_stairs {
+                continue;
+            }
+            let going_up = next_move == Move::Up;
+            match cell_on_next_floor(maze_table, position, going_up) {
+                None => issues.push(MazeIssue::StairsOutOfMaze { position: cell_position, next_move }),
+                Some(other_position) => {
+                    let other_cell = &maze_table[other_position];
+                    if (going_up && !other_cell.stairs_down) || (!going_up && !other_cell.stairs_up) {
+                        issues.push(MazeIssue::AsymmetricStairs { position: cell_position, next_move });
+                    }
+                },
+            }
+        }
+    }
+
+    let cycle = hazard_cycle(maze_table);
+    if cycle > MAX_HAZARD_CYCLE {
+        issues.push(MazeIssue::HazardCycleTooLong { cycle });
+    }
+
+    for (group, group_name) in maze_state.gate_names.iter().enumerate() {
+        let has_gate = maze_state.gates.values().any(|gate_group| *gate_group == group);
+        let has_switch = maze_table.elements_row_major_iter().any(|maze_cell| maze_cell.switch.is_some_and(|switch| switch.group() == group));
+        if has_gate && !has_switch {
+            issues.push(MazeIssue::GateWithoutSwitch { group: group_name.clone() });
+        }
+        if has_switch && !has_gate {
+            issues.push(MazeIssue::SwitchWithoutGate { group: group_name.clone() });
+        }
+    }
+
+    // with several agents, any of them may pick up a key or reach an exit
+    let mut reachable: HashSet<(usize, usize)> = HashSet::new();
+    for agent_position in maze_state.agent_positions() {
+        reachable.extend(find_reachable_cells(maze_table, agent_position));
+    }
+    for key_position in &maze_state.keys_left {
+        if !reachable.contains(key_position) {
+            issues.push(MazeIssue::UnreachableKey { position: floor_position(maze_table, *key_position) });
+        }
+    }
+
+    let mut has_exit = false;
+    for maze_cell in maze_table.elements_row_major_iter() {
+        if maze_cell.end_of_maze {
+            has_exit = true;
+            if !reachable.contains(&(maze_cell.row_index, maze_cell.col_index)) {
+                issues.push(MazeIssue::UnreachableExit { position: floor_position(maze_table, (maze_cell.row_index, maze_cell.col_index)) });
+            }
+        }
+    }
+    if !has_exit {
+        issues.push(MazeIssue::NoExit);
+    }
+
+    return issues;
+}
+
+fn remove_doors(maze_state: &mut MazeState, doors: &HashSet<(usize, usize, Direction)>) {
+    maze_state.cells_with_locked_doors.retain(|x| !doors.contains(x));
+    maze_state.sealed_doors.retain(|x| !doors.contains(x));
+    maze_state.door_colors.retain(|x, _| !doors.contains(x));
+}
+
+fn remove_door(maze_state: &mut MazeState, position: (usize, usize), direction: &Direction) {
+    remove_doors(maze_state, &HashSet::from([(position.0, position.1, direction.clone())]));
+}
+
+// makes walls, doors and stairs symmetric apart from declared one-way passages (door colors and gates that don't match are left to the user), returning the issues that were fixed
+pub fn repair_maze(maze_table: &mut Array2D<MazeCell>, maze_state: &mut MazeState) -> Vec<MazeIssue> {
+    let mut repaired: Vec<MazeIssue> = Vec::new();
+
+    // passages and stairs out of the maze are closed (passages together with their doors), one-way passages are closed
+    // from the other side and the remaining one-sided passages and stairs are opened from both sides
+    for issue in validate_maze(maze_table, maze_state) {
+        match &issue {
+            MazeIssue::BoundaryOpening { position, direction } => {
+                let position = table_position(maze_table, *position);
+                maze_table.get_mut(position.0, position.1).unwrap().available_directions.retain(|x| x != direction);
+                maze_table.get_mut(position.0, position.1).unwrap().one_way.retain(|x| x != direction);
+                remove_door(maze_state, position, direction);
+                let gate = (position.0, position.1, direction.clone());
+                maze_state.gates.remove(&gate);
+                maze_state.open_gates.retain(|x| *x != gate);
+            },
+            MazeIssue::StairsOutOfMaze { position, next_move } => {
+                let position = table_position(maze_table, *position);
+                let maze_cell = maze_table.get_mut(position.0, position.1).unwrap();
+                if *next_move == Move::Up {
+                    maze_cell.stairs_up = false;
+                }
+                else {
+                    maze_cell.stairs_down = false;
+                }
+            },
+            MazeIssue::AsymmetricStairs { position, next_move } => {
+                let position = table_position(maze_table, *position);
+                let other_position = cell_on_next_floor(maze_table, position, *next_move == Move::Up).unwrap();
+                let other_cell = maze_table.get_mut(other_position.0, other_position.1).unwrap();
+                if *next_move == Move::Up {
+                    other_cell.stairs_down = true;
+                }
+                else {
+                    other_cell.stairs_up = true;
+                }
+            },
+            MazeIssue::OneWayOpensBack { position, direction } => {
+                let position = table_position(maze_table, *position);
+                let neighbour_position = neighbour_in_direction(maze_table, position, direction).unwrap();
+                maze_table.get_mut(neighbour_position.0, neighbour_position.1).unwrap().available_directions.retain(|x| *x != direction.opposite());
+            },
+            MazeIssue::AsymmetricWall { position, direction } => {
+                let position = table_position(maze_table, *position);
+                let neighbour_position = neighbour_in_direction(maze_table, position, direction).unwrap();
+                maze_table.get_mut(neighbour_position.0, neighbour_position.1).unwrap().available_directions.push(direction.opposite());
+            },
+            _ => continue,
+        }
+        repaired.push(issue);
+    }
+
+    // with walls symmetric, doors without a passage are dropped (all at once, there can be one on every cell side)
+    // and the remaining doors are locked from both sides
+    let mut doors_without_passage: HashSet<(usize, usize, Direction)> = HashSet::new();
+    for issue in validate_maze(maze_table, maze_state) {
+        match &issue {
+            MazeIssue::DoorWithoutPassage { position, direction } => {
+                let position = table_position(maze_table, *position);
+                doors_without_passage.insert((position.0, position.1, direction.clone()));
+            },
+            _ => continue,
+        }
+        repaired.push(issue);
+    }
+    remove_doors(maze_state, &doors_without_passage);
+    for issue in validate_maze(maze_table, maze_state) {
+        match &issue {
+            MazeIssue::AsymmetricDoor { position, direction } => {
+                let position = table_position(maze_table, *position);
+                let neighbour_position = neighbour_in_direction(maze_table, position, direction).unwrap();
+                maze_state.cells_with_locked_doors.push((neighbour_position.0, neighbour_position.1, direction.opposite()));
+                // the added side gets the color of the existing one
+                let color = maze_state.door_color(position, direction);
+                if color != 0 {
+                    maze_state.door_colors.insert((neighbour_position.0, neighbour_position.1, direction.opposite()), color);
+                }
+            },
+            _ => continue,
+        }
+        repaired.push(issue);
+    }
+
+    return repaired;
+}
@@ -0,0 +1,35 @@
+#![allow(clippy::needless_return)]
+
+use maze_escape_rust::Array2D;
+use maze_escape_rust::maze::{ MazeCell, MazeState };
+use maze_escape_rust::parse::read_maze;
+use maze_escape_rust::solver::SolverRegistry;
+
+fn parse_maze(definition: &str) -> (Array2D<MazeCell>, MazeState) {
+    return read_maze(definition.as_bytes()).unwrap_or_else(|error| panic!("couldn't read maze: {}", error));
+}
+
+// the first key is down to one opening when the second is picked up, and only the second has enough openings
+// for the three doors after the red one, so the worn key has to be dropped to make room for the red key
+const DROP_WORN_KEY: &str = "\
+1 9 keys=3 capacity=2
+0100 0000 0100
+1100 0100 1100
+1100 1100 0000
+1100 1000 1100
+1100 0100 1100 key=red doorE=red
+1100 1100 0000 doorW=red
+1100 1100 0000
+1100 1100 0000
+1000 1000 0011
+";
+
+#[test]
+fn every_solver_drops_the_worn_key() {
+    let (maze_table, initial_maze_state) = parse_maze(DROP_WORN_KEY);
+    let registry = SolverRegistry::with_builtin_solvers(4);
+    for solver in registry.iter() {
+        let solution = solver.solve(&maze_table, &initial_maze_state).unwrap();
+        assert_eq!(solution.move_string(), "EEEXEEEEE", "{}", solver.name());
+    }
+}
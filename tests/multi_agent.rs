@@ -0,0 +1,89 @@
+#![allow(clippy::needless_return)]
+
+use maze_escape_rust::Array2D;
+use maze_escape_rust::maze::{ MazeCell, MazeState };
+use maze_escape_rust::multi_agent::{ MultiAgentSolution, Schedule, solve_maze_multi_agent };
+use maze_escape_rust::parse::read_maze;
+
+fn parse_maze(definition: &str) -> (Array2D<MazeCell>, MazeState) {
+    return read_maze(definition.as_bytes()).unwrap_or_else(|error| panic!("couldn't read maze: {}", error));
+}
+
+// each agent walks two cells east along its own corridor to its exit
+const TWO_CORRIDORS: &str = "\
+2 3 agents=2
+0100 0000 0100
+1100 0000 0000
+1000 0000 0011
+0100 0000 0100
+1100 0000 0000
+1000 0000 0011
+";
+
+// the middle of the first corridor is blocked on odd ticks, so the first agent can't step onto it right away
+fn two_corridors_with_hazard() -> String {
+    return TWO_CORRIDORS.replacen("1100 0000 0000", "1100 0000 0000 hazard=2:1", 1);
+}
+
+fn solve(definition: &str, schedule: Schedule) -> MultiAgentSolution {
+    let (maze_table, initial_maze_state) = parse_maze(definition);
+    return solve_maze_multi_agent(&maze_table, &initial_maze_state, schedule).unwrap();
+}
+
+fn moving_agents(solution: &MultiAgentSolution, step: usize) -> usize {
+    return solution.tracks.iter().filter(|track| track[step].moved).count();
+}
+
+#[test]
+fn joint_schedule_moves_agents_together() {
+    let solution = solve(TWO_CORRIDORS, Schedule::Joint);
+    assert_eq!(solution.makespan(), 2);
+    assert_eq!(solution.total_moves(), 4);
+    assert_eq!(solution.tracks[0].last().unwrap().position, (0, 2));
+    assert_eq!(solution.tracks[1].last().unwrap().position, (1, 2));
+}
+
+#[test]
+fn turn_based_schedule_moves_one_agent_per_step() {
+    let solution = solve(TWO_CORRIDORS, Schedule::TurnBased);
+    assert_eq!(solution.makespan(), 4);
+    assert_eq!(solution.total_moves(), 4);
+    assert!((1..=solution.makespan()).all(|step| moving_agents(&solution, step) == 1));
+}
+
+#[test]
+fn joint_schedule_waits_for_hazard_to_pass() {
+    let solution = solve(&two_corridors_with_hazard(), Schedule::Joint);
+    assert_eq!(solution.makespan(), 3);
+    assert_eq!(solution.total_moves(), 4);
+    assert!(!solution.tracks[0][1].moved);
+}
+
+#[test]
+fn turn_based_schedule_moves_around_hazard() {
+    let solution = solve(&two_corridors_with_hazard(), Schedule::TurnBased);
+    assert_eq!(solution.makespan(), 4);
+    assert_eq!(solution.total_moves(), 4);
+    assert!((1..=solution.makespan()).all(|step| moving_agents(&solution, step) <= 1));
+}
+
+// both agents have to wait a tick before they can step onto the exit, which is blocked on odd ticks
+const WAIT_FOR_HAZARD: &str = "\
+2 2 agents=2
+0100 0000 0100
+1000 0000 0011 hazard=2:1
+0100 0000 0100
+1000 0000 0011 hazard=2:1
+";
+
+#[test]
+fn joint_schedule_waits_out_hazard() {
+    let (maze_table, initial_maze_state) = parse_maze(WAIT_FOR_HAZARD);
+    let solution = solve_maze_multi_agent(&maze_table, &initial_maze_state, Schedule::Joint).unwrap();
+    assert_eq!(solution.makespan(), 2);
+    assert_eq!(solution.total_moves(), 2);
+    for track in solution.tracks.iter() {
+        assert!(!track[1].moved);
+        assert!(track[2].moved);
+    }
+}
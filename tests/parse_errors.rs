@@ -0,0 +1,152 @@
+#![allow(clippy::needless_return)]
+
+use maze_escape_rust::parse::{ read_maze, MazeParseError, MAX_CELLS };
+
+fn parse_error(definition: &[u8]) -> MazeParseError {
+    match read_maze(definition) {
+        Ok(_) => panic!("maze was read without an error: {:?}", String::from_utf8_lossy(definition)),
+        Err(error) => return error,
+    }
+}
+
+#[test]
+fn unreadable_input_is_an_io_error() {
+    assert!(matches!(parse_error(b"1 1\n\xff\xfe 0000 0100\n"), MazeParseError::Io(_)));
+}
+
+#[test]
+fn empty_input_has_no_header() {
+    assert!(matches!(parse_error(b""), MazeParseError::MissingHeader));
+}
+
+#[test]
+fn header_needs_two_positive_numbers() {
+    for header in ["0 3", "3", "three 3", "3 -1"] {
+        let error = parse_error(format!("{}\n0000 0000 0100\n", header).as_bytes());
+        assert!(matches!(&error, MazeParseError::InvalidHeader { line: 1, found } if found == header), "{}", error);
+    }
+}
+
+#[test]
+fn header_over_the_cell_limit_is_too_large() {
+    for header in ["1000 1001", "1000 1000 levels=2", "18446744073709551615 2"] {
+        let error = parse_error(format!("{}\n", header).as_bytes());
+        assert!(matches!(&error, MazeParseError::MazeTooLarge { line: 1, max_cells: MAX_CELLS, found } if found == header), "{}", error);
+    }
+}
+
+#[test]
+fn short_or_glued_cell_line_has_wrong_length() {
+    let error = parse_error(b"1 1\n0000 0000 01\n");
+    assert!(matches!(error, MazeParseError::WrongLineLength { line: 2, expected: 14, found: 12 }), "{}", error);
+    let error = parse_error(b"1 1\n0000 0000 0100cost=2\n");
+    assert!(matches!(error, MazeParseError::WrongLineLength { line: 2, expected: 14, found: 20 }), "{}", error);
+}
+
+#[test]
+fn field_digits_must_be_binary() {
+    let error = parse_error(b"1 2\n0100 0000 0100\n1000 0020 0011\n");
+    assert!(matches!(error, MazeParseError::NonBinaryDigit { line: 3, column: 8, found: '2' }), "{}", error);
+    assert_eq!(error.to_string(), "line 3, column 8: expected '0' or '1', found '2'");
+}
+
+#[test]
+fn fields_must_be_separated_by_spaces() {
+    let error = parse_error(b"1 2\n0100 0000 0100\n1000-0000 0011\n");
+    assert!(matches!(error, MazeParseError::MissingSeparator { line: 3, column: 5, found: '-' }), "{}", error);
+    let error = parse_error(b"1 2\n0100 0000\t0100\n1000 0000 0011\n");
+    assert!(matches!(error, MazeParseError::MissingSeparator { line: 2, column: 10, found: '\t' }), "{}", error);
+}
+
+#[test]
+fn key_and_exit_fields_cant_be_half_set() {
+    let error = parse_error(b"1 2\n0100 0000 0100\n1000 0000 1011\n");
+    assert!(matches!(&error, MazeParseError::HalfSetField { line: 3, column: 11, field: "key", found } if found == "10"), "{}", error);
+    let error = parse_error(b"1 2\n0100 0000 0100\n1000 0000 0001\n");
+    assert!(matches!(&error, MazeParseError::HalfSetField { line: 3, column: 13, field: "exit", found } if found == "01"), "{}", error);
+    let error = parse_error(b"1 2\n0100 0000 0110\n1000 0000 0011\n");
+    assert!(matches!(&error, MazeParseError::HalfSetField { line: 2, column: 13, field: "exit", found } if found == "10"), "{}", error);
+}
+
+#[test]
+fn surplus_cell_lines_are_too_many() {
+    let error = parse_error(b"1 1\n0000 0000 0111\n0000 0000 0000\n0000 0000 0000\n");
+    assert!(matches!(error, MazeParseError::TooManyLines { line: 3, expected: 1, found: 3 }), "{}", error);
+}
+
+#[test]
+fn missing_cell_lines_are_too_few() {
+    let error = parse_error(b"1 2\n0100 0000 0100\n");
+    assert!(matches!(error, MazeParseError::TooFewLines { line: 3, expected: 2, found: 1 }), "{}", error);
+    let error = parse_error(b"1 2 levels=2\n0100 0000 0100\n1000 0000 0011\n");
+    assert!(matches!(error, MazeParseError::TooFewLines { line: 4, expected: 4, found: 2 }), "{}", error);
+}
+
+#[test]
+fn maze_needs_a_start() {
+    assert!(matches!(parse_error(b"1 1\n0000 0000 0011\n"), MazeParseError::MissingStart));
+}
+
+#[test]
+fn single_agent_has_a_single_start() {
+    let error = parse_error(b"1 3\n0100 0000 0100\n1100 0000 0100\n1000 0000 0011\n");
+    assert!(matches!(error, MazeParseError::MultipleStarts { first_line: 2, line: 3 }), "{}", error);
+}
+
+#[test]
+fn every_agent_needs_a_start() {
+    let error = parse_error(b"1 3 agents=3\n0100 0000 0100\n1100 0000 0100\n1000 0000 0011\n");
+    assert!(matches!(error, MazeParseError::WrongNumberOfStarts { expected: 3, found: 2 }), "{}", error);
+}
+
+#[test]
+fn attributes_need_a_name_and_a_value() {
+    for attribute in ["cost", "cost=", "=2"] {
+        let error = parse_error(format!("1 1\n0000 0000 0111 {}\n", attribute).as_bytes());
+        assert!(matches!(&error, MazeParseError::MalformedAttribute { line: 2, found } if found == attribute), "{}", error);
+    }
+    let error = parse_error(b"1 1 keys\n0000 0000 0111\n");
+    assert!(matches!(&error, MazeParseError::MalformedAttribute { line: 1, found } if found == "keys"), "{}", error);
+}
+
+#[test]
+fn unknown_attributes_are_rejected() {
+    let error = parse_error(b"1 1\n0000 0000 0111 color=red\n");
+    assert!(matches!(&error, MazeParseError::UnknownAttribute { line: 2, name } if name == "color"), "{}", error);
+    let error = parse_error(b"1 1 doors=red\n0000 0000 0111\n");
+    assert!(matches!(&error, MazeParseError::UnknownAttribute { line: 1, name } if name == "doors"), "{}", error);
+}
+
+#[test]
+fn attribute_values_are_checked() {
+    let error = parse_error(b"1 1\n0000 0000 0111 cost=0\n");
+    assert!(matches!(&error, MazeParseError::InvalidAttributeValue { line: 2, name, found, .. } if name == "cost" && found == "0"), "{}", error);
+    let error = parse_error(b"1 1\n0000 0000 0111 hazard=2:2\n");
+    assert!(matches!(&error, MazeParseError::InvalidAttributeValue { line: 2, name, found, .. } if name == "hazard" && found == "2:2"), "{}", error);
+    let error = parse_error(b"1 1 capacity=0\n0000 0000 0111\n");
+    assert!(matches!(&error, MazeParseError::InvalidAttributeValue { line: 1, name, found, .. } if name == "capacity" && found == "0"), "{}", error);
+    let error = parse_error(b"1 1 keys=none\n0000 0000 0111\n");
+    assert!(matches!(&error, MazeParseError::InvalidAttributeValue { line: 1, name, found, .. } if name == "keys" && found == "none"), "{}", error);
+}
+
+#[test]
+fn attributes_need_something_to_apply_to() {
+    let error = parse_error(b"1 2\n0100 0000 0100 key=red\n1000 0000 0011\n");
+    assert!(matches!(&error, MazeParseError::AttributeWithoutTarget { line: 2, name, target: "a key on the cell" } if name == "key"), "{}", error);
+    let error = parse_error(b"1 2\n0100 0000 0100 doorE=red\n1000 0000 0011\n");
+    assert!(matches!(&error, MazeParseError::AttributeWithoutTarget { line: 2, name, .. } if name == "doorE"), "{}", error);
+    let error = parse_error(b"1 2\n0100 0000 0100\n1000 0000 0011 tp=a\n");
+    assert!(matches!(&error, MazeParseError::AttributeWithoutTarget { line: 3, name, .. } if name == "tp"), "{}", error);
+}
+
+#[test]
+fn teleporter_needs_a_partner() {
+    let error = parse_error(b"1 3\n0100 0000 0100\n1100 0000 0000 tp=a\n1000 0000 0011\n");
+    assert!(matches!(&error, MazeParseError::UnpairedTeleporter { line: 3, id } if id == "a"), "{}", error);
+}
+
+#[test]
+fn teleporter_links_only_two_pads() {
+    let error = parse_error(b"1 5\n0100 0000 0100\n1100 0000 0000 tp=a\n1100 0000 0000 tp=a\n1100 0000 0000 tp=a\n1000 0000 0011\n");
+    assert!(matches!(&error, MazeParseError::ExtraTeleporter { line: 5, id, first_lines: (3, 4) } if id == "a"), "{}", error);
+}
@@ -0,0 +1,40 @@
+#![allow(clippy::needless_return)]
+
+use maze_escape_rust::Array2D;
+use maze_escape_rust::maze::{ Direction, MazeCell, MazeState, Position };
+use maze_escape_rust::parse::read_maze;
+use maze_escape_rust::solve::solve_maze_bfs;
+use maze_escape_rust::validate::{ validate_maze, MazeIssue };
+
+fn parse_maze(definition: &str) -> (Array2D<MazeCell>, MazeState) {
+    return read_maze(definition.as_bytes()).unwrap_or_else(|error| panic!("couldn't read maze: {}", error));
+}
+
+// stairs up from the start, then east to the exit on the upper floor
+const TWO_FLOORS: &str = "\
+1 2 levels=2
+0000 0000 0100 stairs=U
+0000 0000 0000
+0100 0000 0000 stairs=D
+1000 0000 0011
+";
+
+#[test]
+fn solution_positions_carry_their_floor() {
+    let (maze_table, initial_maze_state) = parse_maze(TWO_FLOORS);
+    let solution = solve_maze_bfs(&maze_table, &initial_maze_state).unwrap();
+    assert_eq!(solution.path, vec![(0, 0), (1, 0), (1, 1)]);
+    assert_eq!(solution.positions, vec![
+        Position { level: 0, row: 0, col: 0 },
+        Position { level: 1, row: 0, col: 0 },
+        Position { level: 1, row: 0, col: 1 },
+    ]);
+}
+
+#[test]
+fn issue_positions_carry_their_floor() {
+    let (maze_table, initial_maze_state) = parse_maze(&TWO_FLOORS.replace("1000 0000 0011", "1100 0000 0011"));
+    let issues = validate_maze(&maze_table, &initial_maze_state);
+    assert_eq!(issues, vec![MazeIssue::BoundaryOpening { position: Position { level: 1, row: 0, col: 1 }, direction: Direction::EAST }]);
+    assert_eq!(issues[0].display(&maze_table).to_string(), "cell (1, 0, 1) opens EAST out of the maze");
+}
@@ -0,0 +1,58 @@
+#![allow(clippy::needless_return)]
+
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use maze_escape_rust::Array2D;
+use maze_escape_rust::maze::{ MazeCell, MazeState };
+use maze_escape_rust::parse::read_maze;
+use maze_escape_rust::solver::SolverRegistry;
+
+const SHIPPED_MAZES: [&str; 4] = ["maze_def.txt", "maze_def_2.txt", "maze_def_regression_unsolved.txt", "maze_def_regression_shortest.txt"];
+
+fn load_maze(filename: &str) -> (Array2D<MazeCell>, MazeState) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(filename);
+    let file = File::open(&path).unwrap_or_else(|error| panic!("couldn't open {}: {}", path.display(), error));
+    return read_maze(BufReader::new(file)).unwrap_or_else(|error| panic!("couldn't read {}: {}", filename, error));
+}
+
+// every built-in solver finds a path of the given length that starts at the start and ends on an exit
+fn assert_every_solver_moves(filename: &str, expected_moves: usize) {
+    let (maze_table, initial_maze_state) = load_maze(filename);
+    let registry = SolverRegistry::with_builtin_solvers(4);
+    for solver in registry.iter() {
+        let solution = solver.solve(&maze_table, &initial_maze_state)
+            .unwrap_or_else(|unsolvable| panic!("{} on {}: {}", solver.name(), filename, unsolvable));
+        assert_eq!(solution.path.len() - 1, expected_moves, "{} on {}", solver.name(), filename);
+        assert_eq!(solution.path[0], initial_maze_state.current_position, "{} on {}", solver.name(), filename);
+        assert!(maze_table[*solution.path.last().unwrap()].end_of_maze, "{} on {}", solver.name(), filename);
+    }
+}
+
+// was unsolvable when the visited set only kept the position and the number of keys held
+#[test]
+fn regression_unsolved_maze_is_solved_by_every_solver() {
+    assert_every_solver_moves("maze_def_regression_unsolved.txt", 6);
+}
+
+// was solved with a longer path for the same reason
+#[test]
+fn regression_shortest_maze_gets_shortest_path_from_every_solver() {
+    assert_every_solver_moves("maze_def_regression_shortest.txt", 5);
+}
+
+#[test]
+fn parallel_solvers_match_sequential_path_length() {
+    for filename in SHIPPED_MAZES {
+        let (maze_table, initial_maze_state) = load_maze(filename);
+        for num_threads in 1..=4 {
+            let registry = SolverRegistry::with_builtin_solvers(num_threads);
+            let sequential = registry.get("sequential").unwrap().solve(&maze_table, &initial_maze_state).unwrap();
+            for name in ["parallel", "channels"] {
+                let solution = registry.get(name).unwrap().solve(&maze_table, &initial_maze_state).unwrap();
+                assert_eq!(solution.path.len(), sequential.path.len(), "{} with {} threads on {}", name, num_threads, filename);
+            }
+        }
+    }
+}
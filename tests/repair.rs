@@ -0,0 +1,104 @@
+#![allow(clippy::needless_return)]
+
+use std::path::Path;
+
+use maze_escape_rust::Array2D;
+use maze_escape_rust::maze::{ Direction, MazeCell, MazeState, Position };
+use maze_escape_rust::parse::{ read_maze, read_maze_from_file };
+use maze_escape_rust::solve::solve_maze_bfs;
+use maze_escape_rust::validate::{ repair_maze, validate_maze, MazeIssue };
+
+fn load_maze(filename: &str) -> (Array2D<MazeCell>, MazeState) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(filename);
+    return read_maze_from_file(path.display().to_string()).unwrap_or_else(|error| panic!("couldn't read {}: {}", filename, error));
+}
+
+fn parse_maze(definition: &str) -> (Array2D<MazeCell>, MazeState) {
+    return read_maze(definition.as_bytes()).unwrap_or_else(|error| panic!("couldn't read maze: {}", error));
+}
+
+fn at(col: usize) -> Position {
+    return Position { level: 0, row: 0, col };
+}
+
+fn has_door(maze_state: &MazeState, position: (usize, usize), direction: Direction) -> bool {
+    return maze_state.cells_with_locked_doors.contains(&(position.0, position.1, direction));
+}
+
+#[test]
+fn repaired_initial_maze_solves_like_maze_def() {
+    let (mut maze_table, mut initial_maze_state) = load_maze("maze_def_initial.txt");
+    let errors = validate_maze(&maze_table, &initial_maze_state).into_iter().filter(MazeIssue::is_error).count();
+    assert_eq!(errors, 12);
+
+    let repaired = repair_maze(&mut maze_table, &mut initial_maze_state);
+    assert_eq!(repaired.len(), errors);
+    let issues = validate_maze(&maze_table, &initial_maze_state);
+    assert!(issues.iter().all(|issue| !issue.is_error()), "{:?}", issues);
+
+    let solution = solve_maze_bfs(&maze_table, &initial_maze_state).unwrap();
+    let (example_table, example_state) = load_maze("maze_def.txt");
+    let example_solution = solve_maze_bfs(&example_table, &example_state).unwrap();
+    assert_eq!(solution.path.len() - 1, 9);
+    assert_eq!(solution.path.len(), example_solution.path.len());
+}
+
+// the boundary pass closes the passage together with its door, so the door isn't reported again as a door without a passage
+#[test]
+fn passage_out_of_the_maze_is_closed_with_its_door() {
+    let (mut maze_table, mut initial_maze_state) = parse_maze("1 2\n1100 1000 0100\n1000 0000 0011\n");
+    let repaired = repair_maze(&mut maze_table, &mut initial_maze_state);
+    assert_eq!(repaired, vec![MazeIssue::BoundaryOpening { position: at(0), direction: Direction::WEST }]);
+    assert_eq!(maze_table[(0, 0)].available_directions, vec![Direction::EAST]);
+    assert!(!has_door(&initial_maze_state, (0, 0), Direction::WEST));
+}
+
+// walls are fixed before doors, so a door on a one-sided passage is mirrored rather than dropped, with its color
+#[test]
+fn one_sided_passage_is_opened_before_its_door_is_mirrored() {
+    let (mut maze_table, mut initial_maze_state) = parse_maze("1 2\n0100 0100 0100 doorE=red\n0000 0000 0011\n");
+    let repaired = repair_maze(&mut maze_table, &mut initial_maze_state);
+    assert_eq!(repaired, vec![
+        MazeIssue::AsymmetricWall { position: at(0), direction: Direction::EAST },
+        MazeIssue::AsymmetricDoor { position: at(0), direction: Direction::EAST },
+    ]);
+    assert_eq!(maze_table[(0, 1)].available_directions, vec![Direction::WEST]);
+    assert!(has_door(&initial_maze_state, (0, 1), Direction::WEST));
+    assert_eq!(initial_maze_state.door_color((0, 1), &Direction::WEST), initial_maze_state.door_color((0, 0), &Direction::EAST));
+    assert!(validate_maze(&maze_table, &initial_maze_state).is_empty());
+}
+
+// doors without a passage are dropped before the remaining doors are mirrored, so this one isn't mirrored first
+#[test]
+fn door_without_a_passage_on_either_side_is_dropped() {
+    let (mut maze_table, mut initial_maze_state) = parse_maze("2 2\n0001 0100 0100\n0000 0000 0000\n0110 0000 0000\n1000 0000 0011\n");
+    let repaired = repair_maze(&mut maze_table, &mut initial_maze_state);
+    assert_eq!(repaired, vec![MazeIssue::DoorWithoutPassage { position: at(0), direction: Direction::EAST }]);
+    assert!(initial_maze_state.cells_with_locked_doors.is_empty());
+}
+
+// a one-way passage and its door stay one-sided, the repair only closes a way back the neighbour opens
+#[test]
+fn one_way_passage_is_kept_and_its_way_back_closed() {
+    let (mut maze_table, mut initial_maze_state) = parse_maze("1 2\n0100 0100 0100 oneway=E\n0000 0000 0011\n");
+    assert!(validate_maze(&maze_table, &initial_maze_state).is_empty());
+    assert!(repair_maze(&mut maze_table, &mut initial_maze_state).is_empty());
+
+    let (mut maze_table, mut initial_maze_state) = parse_maze("1 2\n0100 0000 0100 oneway=E\n1000 0000 0011\n");
+    let repaired = repair_maze(&mut maze_table, &mut initial_maze_state);
+    assert_eq!(repaired, vec![MazeIssue::OneWayOpensBack { position: at(0), direction: Direction::EAST }]);
+    assert_eq!(maze_table[(0, 0)].available_directions, vec![Direction::EAST]);
+    assert!(maze_table[(0, 1)].available_directions.is_empty());
+}
+
+// sealing a door from both sides is only a warning, which the repair leaves alone
+#[test]
+fn door_sealed_from_both_sides_is_left_alone() {
+    let definition = "1 3\n0100 0000 0100\n1100 0100 0000 sealed=E\n1000 1000 0011 sealed=W\n";
+    let (mut maze_table, mut initial_maze_state) = parse_maze(definition);
+    let issues = validate_maze(&maze_table, &initial_maze_state);
+    assert_eq!(issues, vec![MazeIssue::DoorSealedFromBothSides { position: at(1), direction: Direction::EAST }]);
+    assert!(!issues[0].is_error());
+    assert!(repair_maze(&mut maze_table, &mut initial_maze_state).is_empty());
+    assert_eq!(initial_maze_state.sealed_doors.len(), 2);
+}